@@ -67,7 +67,8 @@ impl Add<Point3> for AABB {
     }
 }
 
-/// Pad a range with a small positive non zero value. If the range is reversed (2..0 for example), panic
+/// Pad a range thinner than a small positive non zero value to that width, around its center. If the range is
+/// reversed (2..0 for example), panic
 fn pad_to_minimums(x: Range<f64>) -> Range<f64> {
     const DELTA: f64 = 0.0001;
 
@@ -75,7 +76,9 @@ fn pad_to_minimums(x: Range<f64>) -> Range<f64> {
         if x.end < x.start {
             panic!("Tried to pad reversed (and therefore empty) range")
         } else if x.end - x.start < DELTA {
-            (x.start + DELTA)..(x.end + DELTA)
+            // Grow it around its center, so that a flat object stays inside its box
+            let center: f64 = 0.5 * (x.start + x.end);
+            (center - 0.5 * DELTA)..(center + 0.5 * DELTA)
         } else {
             x.start..x.end
         }
@@ -2,6 +2,7 @@ use std::{
     cmp,
     fs::File,
     io::{BufWriter, Write},
+    sync::Arc,
     thread,
 };
 
@@ -11,12 +12,17 @@ use rand;
 use rayon::prelude::*;
 
 use crate::hittable::Hittable;
+use crate::light::environment::Environment;
+use crate::light::{Light, LightSample, Lighting, power_heuristic};
 use crate::point3::color::write_color;
-use crate::point3::{Point3, Vector3, cross, random_in_unit_disk, unit_vector};
-use crate::ray::Ray;
+use crate::point3::spectrum::{
+    film_xyz_to_rgb, rgb_to_spectrum, sample_wavelength, wavelength_to_film_xyz,
+};
+use crate::point3::{Point3, Vector3, cross, dot, random_in_unit_disk, unit_vector};
+use crate::ray::{MediumStack, Ray, RayCone};
 use crate::{
     hittable::{HitRecord, SurfaceCoordinate},
-    material::{Lambertian, ScatteredRayAndAttenuation},
+    material::{Dielectric, Lambertian, ScatteredRayAndAttenuation, beer_lambert},
     point3::color::{Color, proccess_color},
 };
 
@@ -33,7 +39,14 @@ pub struct Camera {
     defocus_angle: f64,
     defocus_disk_u: Vector3,
     defocus_disk_v: Vector3,
-    background_color: Point3,
+    /// The environment and the delta lights
+    lighting: Lighting,
+    /// Whether each sample follows a single wavelength instead of red, green and blue at the same time
+    spectral: bool,
+    /// The media the camera is inside of, if any
+    media: Option<Arc<MediumStack>>,
+    /// Angle covered by a pixel, in radians
+    pixel_spread: f64,
 }
 
 // Create a few structs to group similar arguments together and reduce the arguments to pass to create_camera
@@ -138,9 +151,41 @@ impl Camera {
             defocus_angle: thin_lens.defocus_angle,
             defocus_disk_u,
             defocus_disk_v,
-            background_color,
+            lighting: Lighting::from_background_color(background_color),
+            spectral: false,
+            media: None,
+            pixel_spread: pixel_delta_u.length() / thin_lens.focus_distance,
         }
     }
+
+    /// Replace the uniform background color given in new() by an environment, for example an ImageEnvironment
+    pub fn with_environment(mut self, environment: Arc<dyn Environment>) -> Camera {
+        self.lighting.environment = environment;
+        self
+    }
+
+    /// Add delta lights (point, spot and directional lights) to the scene
+    pub fn with_lights(mut self, lights: Vec<Arc<dyn Light>>) -> Camera {
+        self.lighting.lights.extend(lights);
+        self
+    }
+
+    /// Render in spectral mode: every camera ray carries a random wavelength, colors of textures and lights are
+    /// turned into spectra and the image accumulates the XYZ of the wavelengths. Slower to converge, but
+    /// dispersion, black bodies and metals behave like they do in reality
+    pub fn with_spectral_rendering(mut self) -> Camera {
+        self.spectral = true;
+        self
+    }
+
+    /// Put the camera inside a dielectric, like under water. Call it once for each medium, from the outermost in.
+    /// medium has to be the material of the object around the camera, so that rays leaving it know they do
+    pub fn inside(mut self, medium: &Dielectric) -> Camera {
+        let mut media: MediumStack = self.media.as_deref().copied().unwrap_or_default();
+        media.push(medium.medium_entry(medium.refraction_index));
+        self.media = Some(Arc::new(media));
+        self
+    }
 }
 
 // Public
@@ -169,13 +214,15 @@ impl Camera {
                 for _ in 0..self.samples_per_pixel {
                     let r: Ray = self.get_ray(i, j);
                     // Instead of making ray color a method of Camera, do it like this.
-                    // To do: make background color a texture
-                    pixel_color =
-                        pixel_color + ray_color(&r, self.max_depth, world, self.background_color);
+                    pixel_color = pixel_color
+                        + self.film_sample(
+                            &r,
+                            ray_color(&r, self.max_depth, world, &self.lighting, None),
+                        );
                 }
                 write_color(
                     &mut image_buffer,
-                    pixel_color / (self.samples_per_pixel as f64),
+                    self.develop(pixel_color / (self.samples_per_pixel as f64)),
                 );
             }
         }
@@ -197,13 +244,16 @@ impl Camera {
                 let pixel_color: Color = (0..self.samples_per_pixel)
                     .map(|_| {
                         let r: Ray = self.get_ray(i, j);
-                        ray_color(&r, self.max_depth, world, self.background_color)
+                        self.film_sample(
+                            &r,
+                            ray_color(&r, self.max_depth, world, &self.lighting, None),
+                        )
                     })
                     .sum();
 
                 write_color(
                     &mut image_buffer,
-                    pixel_color / (self.samples_per_pixel as f64),
+                    self.develop(pixel_color / (self.samples_per_pixel as f64)),
                 );
             }
         }
@@ -225,12 +275,15 @@ impl Camera {
                 let pixel_color: Color = (0..self.samples_per_pixel)
                     .map(|_| {
                         let r: Ray = self.get_ray(i, j);
-                        ray_color(&r, self.max_depth, world, self.background_color)
+                        self.film_sample(
+                            &r,
+                            ray_color(&r, self.max_depth, world, &self.lighting, None),
+                        )
                     })
                     .sum();
                 let pixel: &mut image::Rgb<u8> = image_buffer.get_pixel_mut(i, j);
                 *pixel = image::Rgb(proccess_color(
-                    pixel_color / (self.samples_per_pixel as f64),
+                    self.develop(pixel_color / (self.samples_per_pixel as f64)),
                 ));
             }
         }
@@ -259,11 +312,14 @@ impl Camera {
                     .into_par_iter()
                     .map(|_| {
                         let r: Ray = self.get_ray(i, j);
-                        ray_color2(&r, self.max_depth, world, self.background_color)
+                        self.film_sample(
+                            &r,
+                            ray_color(&r, self.max_depth, world, &self.lighting, None),
+                        )
                     })
                     .sum();
                 *pixel = image::Rgb(proccess_color(
-                    pixel_color / (self.samples_per_pixel as f64),
+                    self.develop(pixel_color / (self.samples_per_pixel as f64)),
                 ));
             });
         println!("\nRender done!");
@@ -279,7 +335,7 @@ impl Camera {
     //                 .into_par_iter()
     //                 .map(|_| {
     //                     let r: Ray = self.get_ray(i, j);
-    //                     ray_color2(&r, self.max_depth, world, self.background_color)
+    //                     ray_color(&r, self.max_depth, world, &self.lighting, None)
     //                 })
     //                 .sum();
     //             image::Rgb(proccess_color(
@@ -291,7 +347,7 @@ impl Camera {
     // }
 }
 
-/// A version of the render function meant to be used in a multithreaded setting. 
+/// A version of the render function meant to be used in a multithreaded setting.
 /// The number will be used to name the image, which will be saved in images/temp/
 pub fn render_two(camera: &Camera, world: &dyn Hittable, samples: u32, number: u32) {
     let mut image_buffer: ImageBuffer<image::Rgb<u8>, Vec<u8>> =
@@ -302,11 +358,16 @@ pub fn render_two(camera: &Camera, world: &dyn Hittable, samples: u32, number: u
             let pixel_color: Color = (0..samples)
                 .map(|_| {
                     let r: Ray = camera.get_ray(i, j);
-                    ray_color(&r, camera.max_depth, world, camera.background_color)
+                    camera.film_sample(
+                        &r,
+                        ray_color(&r, camera.max_depth, world, &camera.lighting, None),
+                    )
                 })
                 .sum();
             let pixel: &mut image::Rgb<u8> = image_buffer.get_pixel_mut(i, j);
-            *pixel = image::Rgb(proccess_color(pixel_color / (samples as f64)));
+            *pixel = image::Rgb(proccess_color(
+                camera.develop(pixel_color / (samples as f64)),
+            ));
         }
     }
     // image_buffer
@@ -315,13 +376,13 @@ pub fn render_two(camera: &Camera, world: &dyn Hittable, samples: u32, number: u
         .unwrap();
 }
 
-// To do: do not save images here, make the threads return image buffers and then convining them with image::par_from_fn to sum 
+// To do: do not save images here, make the threads return image buffers and then convining them with image::par_from_fn to sum
 pub fn thrender_no_rayon(camera: &Camera, world: &(dyn Hittable + Sync + Send)) {
     const MAX_SAMPLES: u32 = 16;
     thread::scope(|s| {
         for i in 0_u32..MAX_SAMPLES {
             s.spawn(move || {
-                render_two(camera, world, camera.samples_per_pixel/MAX_SAMPLES, i);
+                render_two(camera, world, camera.samples_per_pixel / MAX_SAMPLES, i);
             });
         }
     });
@@ -346,92 +407,245 @@ pub fn thrender_no_rayon(camera: &Camera, world: &(dyn Hittable + Sync + Send))
 
 // Private
 
-fn ray_color(given_ray: &Ray, depth: u32, world: &dyn Hittable, background_color: Color) -> Color {
+/// Returns the color seen by given_ray. scattering_pdf is the density with which the material at the previous bounce
+/// chose the direction of given_ray, or None if it was a camera ray or a specular bounce. It is needed to weight
+/// the environment when the ray escapes, since the environment was also sampled directly at that bounce
+pub(crate) fn ray_color(
+    given_ray: &Ray,
+    depth: u32,
+    world: &dyn Hittable,
+    lighting: &Lighting,
+    scattering_pdf: Option<f64>,
+) -> Color {
     if depth == 0 {
         return Color::black();
     }
 
-    let mut hit_record: HitRecord = HitRecord {
-        p: Point3::default(),
-        normal: Point3::default(),
-        material: Lambertian::from_color(Point3::default()),
-        t: 0.0,
-        surface_coords: SurfaceCoordinate { u: 0.0, v: 0.0 },
-        front_face: false,
-    };
+    let mut hit_record: HitRecord = empty_hit_record();
 
     if !world.hit(given_ray, &(0.001..f64::INFINITY), &mut hit_record) {
-        // If the ray hits nothing return the background color
-        return background_color;
+        // If the ray hits nothing return the color of the environment
+        return environment_color(given_ray, &*lighting.environment, scattering_pdf);
     }
 
-    let mut sca_att: ScatteredRayAndAttenuation = ScatteredRayAndAttenuation {
-        scattered_ray: Ray {
-            origin: Point3 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-            },
-            direction: Point3 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            inverse_direction: Point3 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-        },
-        attenuation: Color {
-            x: 1.0,
-            y: 1.0,
-            z: 1.0,
-        },
-    };
+    hit_record.footprint = texture_footprint(given_ray, &hit_record);
 
-    let color_from_emission: Color = hit_record
-        .material
-        .emitted(hit_record.surface_coords, &hit_record.p);
+    // Light coming back from the hit is partly absorbed on the way by the medium the ray is in, like tinted water
+    let transmittance: Color = at_wavelength(
+        medium_transmittance(given_ray, hit_record.t * given_ray.direction.length()),
+        given_ray.wavelength,
+    );
+
+    let mut sca_att: ScatteredRayAndAttenuation = empty_scattered_ray();
+
+    let color_from_emission: Color = emission(&hit_record, given_ray.wavelength);
 
     if !hit_record
         .material
         .scatter(given_ray, &hit_record, &mut sca_att)
     {
-        return color_from_emission;
+        return transmittance * color_from_emission;
+    }
+
+    // Once a dispersive material has chosen a wavelength the rest of the path keeps it
+    if sca_att.scattered_ray.wavelength.is_none() {
+        sca_att.scattered_ray.wavelength = given_ray.wavelength;
     }
+    // And the media stay the same until a dielectric changes them
+    if sca_att.scattered_ray.media.is_none() {
+        sca_att.scattered_ray.media = given_ray.media.clone();
+    }
+    // Mirrors and glass keep the cone of the ray. Other bounces go in random directions, which blur the textures
+    // they see anyway
+    if sca_att.pdf == 0.0 {
+        sca_att.scattered_ray.cone = given_ray.cone.map(|cone| RayCone {
+            width: cone.width_at(hit_record.t * given_ray.direction.length()),
+            spread: cone.spread,
+        });
+    }
+
+    // Specular materials can not be evaluated in an arbitrary direction, so there is no point in sampling lights for them
+    let (color_from_lights, next_scattering_pdf) = if sca_att.pdf > 0.0 {
+        (
+            sample_lights(given_ray, &hit_record, world, lighting),
+            Some(sca_att.pdf),
+        )
+    } else {
+        (Color::black(), None)
+    };
 
-    let color_from_scatter: Color =
-        sca_att.attenuation * ray_color(&sca_att.scattered_ray, depth - 1, world, background_color);
+    let color_from_scatter: Color = at_wavelength(sca_att.attenuation, given_ray.wavelength)
+        * ray_color(
+            &sca_att.scattered_ray,
+            depth - 1,
+            world,
+            lighting,
+            next_scattering_pdf,
+        );
 
-    color_from_emission + color_from_scatter
+    transmittance * (color_from_emission + color_from_lights + color_from_scatter)
 }
 
-fn ray_color2(
+/// The color of the environment seen by a ray that escaped, weighted against the direct sampling of the environment
+fn environment_color(
     given_ray: &Ray,
-    depth: u32,
-    world: &(dyn Hittable + Sync + Send),
-    background_color: Color,
+    environment: &dyn Environment,
+    scattering_pdf: Option<f64>,
 ) -> Color {
-    if depth == 0 {
+    let color: Color = at_wavelength(
+        environment.value(&given_ray.direction),
+        given_ray.wavelength,
+    );
+
+    match scattering_pdf {
+        Some(pdf) => power_heuristic(pdf, environment.pdf(&given_ray.direction)) * color,
+        None => color,
+    }
+}
+
+/// Next event estimation: shoot a shadow ray towards each delta light and towards a direction chosen by the
+/// environment, and return the light arriving from them that the material sends back along given_ray
+fn sample_lights(
+    given_ray: &Ray,
+    hit_record: &HitRecord,
+    world: &dyn Hittable,
+    lighting: &Lighting,
+) -> Color {
+    let mut light_sample: LightSample = LightSample::default();
+
+    // Delta lights can not be hit by scattered rays, so they don't need to be weighted
+    let mut color_from_delta_lights: Color = Color::black();
+    for light in &lighting.lights {
+        if light.sample(&hit_record.p, &mut light_sample) {
+            color_from_delta_lights = color_from_delta_lights
+                + unoccluded_light(given_ray, hit_record, world, &light_sample);
+        }
+    }
+
+    if !lighting.environment.sample(&mut light_sample) {
+        return color_from_delta_lights;
+    }
+
+    // Weight the environment with multiple importance sampling against the material choosing the same direction
+    let scattering_pdf: f64 =
+        hit_record
+            .material
+            .scattering_pdf(given_ray, hit_record, &light_sample.direction);
+    let weight: f64 = power_heuristic(light_sample.pdf, scattering_pdf);
+
+    color_from_delta_lights + weight * unoccluded_light(given_ray, hit_record, world, &light_sample)
+}
+
+/// The light of light_sample that the material sends back along given_ray, or black if something blocks the light
+fn unoccluded_light(
+    given_ray: &Ray,
+    hit_record: &HitRecord,
+    world: &dyn Hittable,
+    light_sample: &LightSample,
+) -> Color {
+    let scattering: Color = at_wavelength(
+        hit_record
+            .material
+            .evaluate(given_ray, hit_record, &light_sample.direction),
+        given_ray.wavelength,
+    );
+
+    if scattering.is_near_zero() {
         return Color::black();
     }
 
-    let mut hit_record: HitRecord = HitRecord {
+    // Shadow ray. It leaves in the media of given_ray, which absorb part of the light on its way
+    let shadow_ray: Ray =
+        Ray::new(hit_record.p, light_sample.direction).with_media(given_ray.media.clone());
+    let mut shadow_record: HitRecord = empty_hit_record();
+    if world.hit(
+        &shadow_ray,
+        &(0.001..light_sample.distance),
+        &mut shadow_record,
+    ) {
+        return Color::black();
+    }
+    let transmittance: Color = at_wavelength(
+        medium_transmittance(&shadow_ray, light_sample.distance),
+        given_ray.wavelength,
+    );
+
+    transmittance * scattering * at_wavelength(light_sample.radiance, given_ray.wavelength)
+        / light_sample.pdf
+}
+
+/// Fraction of the light that survives travelling distance along ray through the medium it is in (Beer-Lambert law).
+/// White outside of any medium
+fn medium_transmittance(ray: &Ray, distance: f64) -> Color {
+    match ray.media.as_deref().and_then(MediumStack::current) {
+        Some(medium) => beer_lambert(&medium.absorption, distance),
+        None => Color::white(),
+    }
+}
+
+/// Size of the cone of given_ray where it hits, along the surface coordinates
+fn texture_footprint(given_ray: &Ray, hit_record: &HitRecord) -> SurfaceCoordinate {
+    let cone: RayCone = match given_ray.cone {
+        Some(cone) => cone,
+        None => return SurfaceCoordinate { u: 0.0, v: 0.0 },
+    };
+    let u_length: f64 = hit_record.dp_du.length();
+    let v_length: f64 = hit_record.dp_dv.length();
+    if u_length == 0.0 || v_length == 0.0 {
+        return SurfaceCoordinate { u: 0.0, v: 0.0 };
+    }
+
+    // Seen at a grazing angle the cone covers a longer area. Filtering can only use one size, so take the middle
+    // between the width across and the width along the surface
+    let cos_theta: f64 = dot(&unit_vector(given_ray.direction), &hit_record.normal)
+        .abs()
+        .max(0.05);
+    let width: f64 = cone.width_at(hit_record.t * given_ray.direction.length()) / cos_theta.sqrt();
+
+    SurfaceCoordinate {
+        u: width / u_length,
+        v: width / v_length,
+    }
+}
+
+/// A path that carries a single wavelength sees every color as the value of its spectrum at that wavelength
+fn at_wavelength(color: Color, wavelength: Option<f64>) -> Color {
+    match wavelength {
+        Some(wavelength) => {
+            let value: f64 = rgb_to_spectrum(&color, wavelength);
+            Color::new(value, value, value)
+        }
+        None => color,
+    }
+}
+
+/// Light emitted by the material at the hit point, at the wavelength of the path if it has one
+fn emission(hit_record: &HitRecord, wavelength: Option<f64>) -> Color {
+    match wavelength {
+        Some(wavelength) => {
+            let value: f64 = hit_record.material.emitted_spectrum(hit_record, wavelength);
+            Color::new(value, value, value)
+        }
+        None => hit_record.material.emitted(hit_record),
+    }
+}
+
+fn empty_hit_record() -> HitRecord {
+    HitRecord {
         p: Point3::default(),
         normal: Point3::default(),
         material: Lambertian::from_color(Point3::default()),
         t: 0.0,
         surface_coords: SurfaceCoordinate { u: 0.0, v: 0.0 },
         front_face: false,
-    };
-
-    if !world.hit(given_ray, &(0.001..f64::INFINITY), &mut hit_record) {
-        // If the ray hits nothing return the background color
-        return background_color;
+        dp_du: Point3::default(),
+        dp_dv: Point3::default(),
+        footprint: SurfaceCoordinate { u: 0.0, v: 0.0 },
     }
+}
 
-    let mut sca_att: ScatteredRayAndAttenuation = ScatteredRayAndAttenuation {
+fn empty_scattered_ray() -> ScatteredRayAndAttenuation {
+    ScatteredRayAndAttenuation {
         scattered_ray: Ray {
             origin: Point3 {
                 x: 0.0,
@@ -448,29 +662,17 @@ fn ray_color2(
                 y: 1.0,
                 z: 1.0,
             },
+            wavelength: None,
+            media: None,
+            cone: None,
         },
         attenuation: Color {
             x: 1.0,
             y: 1.0,
             z: 1.0,
         },
-    };
-
-    let color_from_emission: Color = hit_record
-        .material
-        .emitted(hit_record.surface_coords, &hit_record.p);
-
-    if !hit_record
-        .material
-        .scatter(given_ray, &hit_record, &mut sca_att)
-    {
-        return color_from_emission;
+        pdf: 0.0,
     }
-
-    let color_from_scatter: Color =
-        sca_att.attenuation * ray_color(&sca_att.scattered_ray, depth - 1, world, background_color);
-
-    color_from_emission + color_from_scatter
 }
 
 impl Camera {
@@ -487,8 +689,37 @@ impl Camera {
             self.defocus_disk_sample()
         };
         let ray_direction: Point3 = pixel_sample - ray_origin;
+        let wavelength: Option<f64> = if self.spectral {
+            Some(sample_wavelength())
+        } else {
+            None
+        };
 
         Ray::new(ray_origin, ray_direction)
+            .with_wavelength(wavelength)
+            .with_media(self.media.clone())
+            .with_cone(Some(RayCone {
+                width: 0.0,
+                spread: self.pixel_spread,
+            }))
+    }
+
+    /// What a sample adds to the film given the radiance that its camera ray r brought back.
+    /// In spectral mode the film collects XYZ instead of rgb
+    fn film_sample(&self, r: &Ray, radiance: Color) -> Color {
+        match (self.spectral, r.wavelength) {
+            (true, Some(wavelength)) => wavelength_to_film_xyz(radiance.x, wavelength),
+            _ => radiance,
+        }
+    }
+
+    /// Turn the average of the samples of a pixel into linear rgb
+    fn develop(&self, film_color: Color) -> Color {
+        if self.spectral {
+            film_xyz_to_rgb(film_color)
+        } else {
+            film_color
+        }
     }
 
     fn defocus_disk_sample(&self) -> Vector3 {
@@ -5,7 +5,7 @@ use rand::random_range;
 use crate::{
     aabb::AABB,
     hittable::{HitRecord, Hittable, SurfaceCoordinate},
-    material::{Isotropic, Material},
+    material::{HenyeyGreenstein, Isotropic, Material},
     point3::{Vector3, color::Color},
     ray::Ray,
     texture::{self, SolidColor, Texture},
@@ -40,59 +40,119 @@ impl ConstantMedium {
             phase_function: Arc::new(Isotropic { texture }),
         }
     }
+
+    /// A medium that scatters light mostly forward (g > 0) or mostly back (g < 0), following the Henyey-Greenstein
+    /// phase function. Forward scattering fog makes halos around lights
+    pub fn anisotropic(
+        boundary: Arc<dyn Hittable>,
+        density: f64,
+        color: Color,
+        g: f64,
+    ) -> ConstantMedium {
+        ConstantMedium {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function: HenyeyGreenstein::new(SolidColor::new(color), g),
+        }
+    }
+
+    /// Use another phase function, like a HenyeyGreenstein with two lobes
+    pub fn with_phase_function(mut self, phase_function: Arc<dyn Material>) -> ConstantMedium {
+        self.phase_function = phase_function;
+        self
+    }
 }
 
 impl Hittable for ConstantMedium {
     fn hit(&self, ray: &Ray, ray_t: &Range<f64>, hit_record: &mut HitRecord) -> bool {
-        let mut hit_record1: HitRecord = hit_record.clone();
-        let mut hit_record2: HitRecord = hit_record.clone();
+        let ray_length: f64 = ray.direction.length();
+        let x: f64 = random_range(0.0..1.0);
+        // Distance travelled inside the boundary before the ray hits a particle
+        let mut hit_distance: f64 = self.neg_inv_density * x.ln();
+        let mut from: f64 = ray_t.start;
 
-        if !self
-            .boundary
-            .hit(ray, &(-f64::INFINITY..f64::INFINITY), &mut hit_record1)
+        while let Some((t_enter, t_exit)) =
+            next_interval_inside(&*self.boundary, ray, from..ray_t.end, hit_record)
         {
-            return false;
+            let distance_inside_boundary: f64 = (t_exit - t_enter) * ray_length;
+
+            if hit_distance <= distance_inside_boundary {
+                set_medium_hit(
+                    hit_record,
+                    ray,
+                    t_enter + hit_distance / ray_length,
+                    &self.phase_function,
+                );
+                return true;
+            }
+
+            hit_distance -= distance_inside_boundary;
+            from = t_exit + 0.0001;
         }
 
-        if !self.boundary.hit(
+        false
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        self.boundary.bounding_box()
+    }
+}
+
+/// The parameters where ray goes into and out of boundary for the first time in range, or None if it is not inside
+/// it there. The boundary must be closed: a ray whose first hit is on a back face started inside, like the rays of a
+/// camera in fog. Call it again after the exit for boundaries that are not convex
+pub fn next_interval_inside(
+    boundary: &dyn Hittable,
+    ray: &Ray,
+    range: Range<f64>,
+    hit_record: &HitRecord,
+) -> Option<(f64, f64)> {
+    let mut hit_record1: HitRecord = hit_record.clone();
+
+    if !boundary.hit(ray, &(range.start..f64::INFINITY), &mut hit_record1) {
+        return None;
+    }
+
+    let (t_enter, t_exit): (f64, f64) = if hit_record1.front_face {
+        let mut hit_record2: HitRecord = hit_record.clone();
+        if !boundary.hit(
             ray,
             &(hit_record1.t + 0.0001..f64::INFINITY),
             &mut hit_record2,
         ) {
-            return false;
+            return None;
         }
-
-        hit_record1.t = hit_record1.t.max(ray_t.start);
-        hit_record2.t = hit_record2.t.min(ray_t.end);
-
-        hit_record1.t = hit_record1.t.max(0.0);
-
-        let ray_length: f64 = ray.direction.length();
-        let distance_inside_boundary: f64 = (hit_record2.t - hit_record1.t) * ray_length;
-        let x: f64 = random_range(0.0..1.0);
-        let hit_distance: f64 = self.neg_inv_density * x.ln();
-
-        if hit_distance > distance_inside_boundary {
-            return false;
-        }
-
-        hit_record.t = hit_record1.t + hit_distance / ray_length;
-        hit_record.p = ray.at(hit_record.t);
-
-        hit_record.normal = Vector3 {
-            x: 1.0,
-            y: 0.0,
-            z: 0.0,
-        }; // Arbitrary
-        hit_record.front_face = true; // Also arbitrary
-        hit_record.surface_coords = SurfaceCoordinate { u: 0.0, v: 0.0 };
-
-        hit_record.material = self.phase_function.clone();
-
-        true
+        (hit_record1.t, hit_record2.t)
+    } else {
+        (range.start, hit_record1.t)
+    };
+    let t_exit: f64 = t_exit.min(range.end);
+
+    if t_enter >= t_exit {
+        return None;
     }
+    Some((t_enter, t_exit))
+}
 
-    fn bounding_box(&self) -> &AABB {
-        self.boundary.bounding_box()
-    }
+/// Fill hit_record with a scattering event at parameter t of ray, inside a medium with phase_function
+pub fn set_medium_hit(
+    hit_record: &mut HitRecord,
+    ray: &Ray,
+    t: f64,
+    phase_function: &Arc<dyn Material>,
+) {
+    hit_record.t = t;
+    hit_record.p = ray.at(t);
+
+    hit_record.normal = Vector3 {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    }; // Arbitrary
+    hit_record.front_face = true; // Also arbitrary
+    hit_record.surface_coords = SurfaceCoordinate { u: 0.0, v: 0.0 };
+    hit_record.dp_du = Vector3::default();
+    hit_record.dp_dv = Vector3::default();
+
+    hit_record.material = phase_function.clone();
 }
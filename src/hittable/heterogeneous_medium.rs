@@ -0,0 +1,150 @@
+use std::{ops::Range, sync::Arc};
+
+use rand::random_range;
+
+use crate::{
+    aabb::AABB,
+    hittable::{
+        HitRecord, Hittable, SurfaceCoordinate,
+        constant_medium::{next_interval_inside, set_medium_hit},
+    },
+    material::{Isotropic, Material},
+    perlin::PerlinNoise,
+    point3::{Point3, color::Color},
+    ray::Ray,
+    texture::{SolidColor, Texture},
+};
+
+/// A density that changes from point to point, like noise or a voxel grid
+pub trait DensityField: Send + Sync {
+    fn density(&self, p: &Point3) -> f64;
+    /// An upper bound of the density anywhere in the field. The closer it is to the real maximum, the faster the
+    /// medium is to render
+    fn max_density(&self) -> f64;
+}
+
+/// Perlin turbulence, for clouds and smoke. The density goes from 0 to max_density, where the turbulence is 1 or more
+pub struct TurbulenceDensity {
+    pub perlin_noise: PerlinNoise,
+    /// Number of times the noise is repeated per unit of distance
+    pub frequency: f64,
+    pub octaves: i32,
+    pub max_density: f64,
+}
+
+impl DensityField for TurbulenceDensity {
+    fn density(&self, p: &Point3) -> f64 {
+        let turbulence: f64 = self
+            .perlin_noise
+            .turbulence(&(self.frequency * *p), self.octaves);
+
+        self.max_density * turbulence.min(1.0)
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max_density
+    }
+}
+
+/// The luminance of a solid texture (clamped to 0..1) times max_density
+pub struct TextureDensity {
+    pub texture: Arc<dyn Texture>,
+    pub max_density: f64,
+}
+
+impl DensityField for TextureDensity {
+    fn density(&self, p: &Point3) -> f64 {
+        // Only solid textures make sense here, as there is no surface
+        let surface_coords: SurfaceCoordinate = SurfaceCoordinate { u: 0.0, v: 0.0 };
+
+        self.max_density
+            * self
+                .texture
+                .value(surface_coords, p)
+                .luminance()
+                .clamp(0.0, 1.0)
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max_density
+    }
+}
+
+/// A medium whose density changes inside its boundary, like a cloud, a smoke plume or patchy fog.
+/// Distances are sampled with delta tracking (Woodcock tracking): the medium is filled with fictitious particles up
+/// to the majorant (the maximum density), which makes it homogeneous, and collisions with the fictitious ones are
+/// ignored. The result is exact, and shadow rays going through it are stopped with the right probability
+pub struct HeterogeneousMedium {
+    pub boundary: Arc<dyn Hittable>,
+    pub density: Arc<dyn DensityField>,
+    pub phase_function: Arc<dyn Material>,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(
+        boundary: Arc<dyn Hittable>,
+        density: Arc<dyn DensityField>,
+        texture: Arc<dyn Texture>,
+    ) -> HeterogeneousMedium {
+        HeterogeneousMedium {
+            boundary,
+            density,
+            phase_function: Arc::new(Isotropic { texture }),
+        }
+    }
+
+    pub fn from_color(
+        boundary: Arc<dyn Hittable>,
+        density: Arc<dyn DensityField>,
+        color: Color,
+    ) -> HeterogeneousMedium {
+        HeterogeneousMedium::new(boundary, density, SolidColor::new(color))
+    }
+
+    /// Use another phase function, like a HenyeyGreenstein
+    pub fn with_phase_function(mut self, phase_function: Arc<dyn Material>) -> HeterogeneousMedium {
+        self.phase_function = phase_function;
+        self
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, ray: &Ray, ray_t: &Range<f64>, hit_record: &mut HitRecord) -> bool {
+        let majorant: f64 = self.density.max_density();
+        if majorant <= 0.0 {
+            return false;
+        }
+
+        let ray_length: f64 = ray.direction.length();
+        let mut from: f64 = ray_t.start;
+
+        while let Some((t_enter, t_exit)) =
+            next_interval_inside(&*self.boundary, ray, from..ray_t.end, hit_record)
+        {
+            let mut t: f64 = t_enter;
+
+            loop {
+                let x: f64 = random_range(0.0..1.0);
+                t -= (1.0 - x).ln() / (majorant * ray_length);
+
+                if t >= t_exit {
+                    break;
+                }
+
+                // A real collision with probability density over majorant, otherwise a fictitious one
+                if random_range(0.0..1.0) * majorant < self.density.density(&ray.at(t)) {
+                    set_medium_hit(hit_record, ray, t, &self.phase_function);
+                    return true;
+                }
+            }
+
+            from = t_exit + 0.0001;
+        }
+
+        false
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        self.boundary.bounding_box()
+    }
+}
@@ -3,8 +3,8 @@ use std::io::{BufRead, BufReader};
 use std::sync::Arc;
 
 use crate::bvh::BVHNode;
-use crate::hittable::Hittable;
 use crate::hittable::triangle::Triangle;
+use crate::hittable::{Hittable, SurfaceCoordinate};
 use crate::material::Material;
 use crate::point3::{Point3, Vector3};
 
@@ -17,11 +17,12 @@ pub fn load_model(model_path: &str, scale: f64, material: Arc<dyn Material>) ->
     let lines: std::io::Lines<BufReader<File>> = BufReader::new(file).lines();
 
     let mut vertex_coords: Vec<Vec<f64>> = Vec::new();
-    let mut faces: Vec<Vec<usize>> = Vec::new();
+    let mut texture_coords: Vec<SurfaceCoordinate> = Vec::new();
+    // Index of the vertex and of its texture coordinates, if it has them
+    let mut faces: Vec<Vec<(usize, Option<usize>)>> = Vec::new();
     // let mut vertex_normals: Vec<Vec<f64>> = Vec::new();
 
     let mut seen_normals: bool = true;
-    let mut seen_texture_coords: bool = true;
 
     for line in lines.map_while(Result::ok) {
         let mut line_iter = line.split_ascii_whitespace();
@@ -35,10 +36,17 @@ pub fn load_model(model_path: &str, scale: f64, material: Arc<dyn Material>) ->
                     vertex_coords.push(coords);
                 }
                 "f" => {
-                    // the format can be like this: f v1/vt1/vn1 v2/vt2/vn2 v3/vt3/vn3 so only get first, which is the vertex
-                    let vertices: Vec<usize> = line_iter
-                        .map(|s| s.split("/").next().unwrap().parse::<usize>().unwrap())
-                        .map(|i| i - 1) // normalize into 0 index
+                    // the format can be like this: f v1/vt1/vn1 v2/vt2/vn2 v3/vt3/vn3 so only get the vertex and
+                    // the texture coordinates. The latter can be missing, like in f v1//vn1
+                    let vertices: Vec<(usize, Option<usize>)> = line_iter
+                        .map(|s| {
+                            let mut indices = s.split("/");
+                            let vertex: usize = indices.next().unwrap().parse::<usize>().unwrap();
+                            let texture: Option<usize> =
+                                indices.next().and_then(|t| t.parse::<usize>().ok());
+                            // normalize into 0 index
+                            (vertex - 1, texture.map(|t| t - 1))
+                        })
                         .collect();
                     // if vertices.len() != 3 && vertices.len() != 4 {
                     //     panic!("Only triangles and parallelogram polygons are supported")
@@ -60,11 +68,16 @@ pub fn load_model(model_path: &str, scale: f64, material: Arc<dyn Material>) ->
                 } // ignore normals
                 "#" => (), // ignore comment line
                 "vt" => {
-                    if seen_texture_coords {
-                        eprintln!("Ignoring texture coordinates");
-                        seen_texture_coords = false;
+                    // There can be a third coordinate, which is ignored
+                    let coords: Vec<f64> = line_iter.map(|s| s.parse::<f64>().unwrap()).collect();
+                    if coords.len() < 2 {
+                        panic!("unable to parse texture coordinates with less than two values");
                     }
-                } // ignore texture coordinates
+                    texture_coords.push(SurfaceCoordinate {
+                        u: coords[0],
+                        v: coords[1],
+                    });
+                }
                 "s" => eprintln!("Smooth shading is not supported, ignoring"),
                 "o" => eprintln!("Loading object with name {}", line_iter.collect::<String>()),
                 "vp" => eprintln!("Free form geometries are not supported"),
@@ -85,22 +98,29 @@ pub fn load_model(model_path: &str, scale: f64, material: Arc<dyn Material>) ->
     };
 
     // triangulate polygons with more than three vertices by supposing that they are convex and going around in a fan https://en.wikipedia.org/wiki/Fan_triangulation
-    let get_triangles = |face: &Vec<usize>| -> Vec<Arc<dyn Hittable>> {
-        let v1: Point3 = point_from_vec(&vertex_coords[face[0]]);
+    let get_triangles = |face: &Vec<(usize, Option<usize>)>| -> Vec<Arc<dyn Hittable>> {
+        let v1: Point3 = point_from_vec(&vertex_coords[face[0].0]);
 
         let mut triangles_vec: Vec<Arc<dyn Hittable>> = Vec::new();
 
         // Weirldly, .windows returns slices, not something with a garanteed size
         for slice in face[1..].windows(2) {
-            let v2: Point3 = point_from_vec(&vertex_coords[slice[0]]);
-            let v3: Point3 = point_from_vec(&vertex_coords[slice[1]]);
+            let v2: Point3 = point_from_vec(&vertex_coords[slice[0].0]);
+            let v3: Point3 = point_from_vec(&vertex_coords[slice[1].0]);
+
+            let triangle: Triangle = Triangle::from_vertex_locations(v1, v2, v3, material.clone());
+
+            // Only use the texture coordinates if all three vertices have them
+            let triangle: Triangle = match (face[0].1, slice[0].1, slice[1].1) {
+                (Some(t1), Some(t2), Some(t3)) => triangle.with_texture_coordinates([
+                    texture_coords[t1],
+                    texture_coords[t2],
+                    texture_coords[t3],
+                ]),
+                _ => triangle,
+            };
 
-            triangles_vec.push(Arc::new(Triangle::from_vertex_locations(
-                v1,
-                v2,
-                v3,
-                material.clone(),
-            )))
+            triangles_vec.push(Arc::new(triangle))
         }
         triangles_vec
     };
@@ -28,6 +28,13 @@ pub struct HitRecord {
     pub surface_coords: SurfaceCoordinate,
     /// Whether the intersection is on the front or back of the surface
     pub front_face: bool,
+    /// Derivatives of the point with respect to the surface coordinates. Together with the normal they make the
+    /// tangent frame used by normal and bump maps. Zero if the surface has no coordinates
+    pub dp_du: Vector3,
+    pub dp_dv: Vector3,
+    /// Size, along u and v, of the area around the point that the ray sees. Image textures use it to filter.
+    /// Zero if unknown
+    pub footprint: SurfaceCoordinate,
 }
 
 /// Creates a HitRecord given some of it's parameters:
@@ -58,6 +65,13 @@ impl HitRecord {
     }
 }
 
+/// Whether a ray goes through the surface at this point instead of hitting it, because the material is cut out there.
+/// Partially opaque points are random, so on average the right fraction of rays goes through
+pub fn is_cut_out(material: &dyn Material, surface_coords: SurfaceCoordinate, p: &Point3) -> bool {
+    let opacity: f64 = material.opacity(surface_coords, p);
+    opacity < 1.0 && rand::random_range(0.0..1.0) >= opacity
+}
+
 // Instead of inheritance, create a trait that subsecuent objects will implement
 // To do: Think about traits versus enums for objects. Eg: scene object hittable that contains sphere, quadrilateral, disk etc
 // Having every object be a variant of Hittable could allow to have a
@@ -130,6 +144,8 @@ impl Hittable for RotateY {
         // Transform the intersection from object space back to world space.
         hit_record.p = rotate_y(&hit_record.p, self.cos_theta, -self.sin_theta);
         hit_record.normal = rotate_y(&hit_record.normal, self.cos_theta, -self.sin_theta);
+        hit_record.dp_du = rotate_y(&hit_record.dp_du, self.cos_theta, -self.sin_theta);
+        hit_record.dp_dv = rotate_y(&hit_record.dp_dv, self.cos_theta, -self.sin_theta);
 
         true
     }
@@ -192,9 +208,11 @@ impl RotateY {
 // Making a hittable bigger is the same as making the ray smaller. You would also have to scale up the bounding box
 
 pub mod constant_medium;
+pub mod heterogeneous_medium;
 pub mod hittable_list;
 pub mod load_obj;
 pub mod parallelogram;
 pub mod quadric;
 pub mod sphere;
 pub mod triangle;
+pub mod voxel_grid;
@@ -3,7 +3,7 @@ use std::sync::Arc;
 
 use crate::aabb::{AABB, join_aabbs};
 use crate::hittable::hittable_list::HittableList;
-use crate::hittable::{HitRecord, Hittable, SurfaceCoordinate};
+use crate::hittable::{HitRecord, Hittable, SurfaceCoordinate, is_cut_out};
 use crate::material::Material;
 use crate::point3::{Point3, Vector3, cross, dot, unit_vector};
 use crate::ray::Ray;
@@ -120,12 +120,19 @@ impl Hittable for Parallelogram {
         }
 
         let surface_coords: SurfaceCoordinate = SurfaceCoordinate { u: alpha, v: beta };
+        // Cut out parts let the ray through, so the search continues with the surfaces behind
+        if is_cut_out(&*self.material, surface_coords, &intersection) {
+            return false;
+        }
+
         hit_record.surface_coords = surface_coords;
 
         hit_record.t = t;
         hit_record.p = intersection;
         hit_record.material = self.material.clone();
         hit_record.set_face_normal(ray, self.normal);
+        hit_record.dp_du = self.u;
+        hit_record.dp_dv = self.v;
 
         true
     }
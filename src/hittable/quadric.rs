@@ -5,7 +5,7 @@ use std::sync::Arc;
 
 use crate::{
     aabb::AABB,
-    hittable::{HitRecord, Hittable, SurfaceCoordinate},
+    hittable::{HitRecord, Hittable, SurfaceCoordinate, is_cut_out},
     material::Material,
     point3::{Point3, Vector3, dot, unit_vector},
     ray::Ray,
@@ -43,45 +43,52 @@ impl Hittable for Quadric {
 
         let sqrt_discriminant: f64 = f64::sqrt(discriminant);
 
-        // Find the nearest root that lies in the acceptable range
-        let mut root: f64 = (h - sqrt_discriminant) / a;
-
-        if !ray_t.contains(&root) {
-            root = (h + sqrt_discriminant) / a;
+        // Find the nearest root that lies in the acceptable range. If the quadric is cut out there, try the far one
+        for root in [(h - sqrt_discriminant) / a, (h + sqrt_discriminant) / a] {
             if !ray_t.contains(&root) {
-                return false;
+                continue;
             }
+
+            let p: Point3 = ray.at(root);
+            // To do: Since there is no general closed form coordinates, find some other way to get surface coordinates. Use differential geomety?
+            // Until then only solid textures can cut out quadrics
+            let surface_coords: SurfaceCoordinate = SurfaceCoordinate { u: 0.0, v: 0.0 };
+
+            if is_cut_out(&*self.material, surface_coords, &p) {
+                continue;
+            }
+
+            // This was also obtained with scripts/sympy_quadric.py
+            let normal: Vector3 = Vector3 {
+                x: 2.0 * self.p1.x * p.x + self.p2.x * p.y + self.p2.y * p.z,
+                y: 2.0 * self.p1.y * p.y + self.p2.x * p.x + self.p2.z * p.z,
+                z: 2.0 * self.p1.z * p.z + self.p2.y * p.x + self.p2.z * p.y,
+            } + self.p3;
+
+            hit_record.t = root;
+            hit_record.p = p;
+            // Make the normal unit lenght
+            let outward_normal: Point3 = unit_vector(normal);
+            // Leave this here to remember, this is already done in set face normal
+            // If the ray originates inside of the surface, reverse the normal
+            // let outward_normal: Point3 = if dot(&d, &p) > 0.0 {
+            //     -unit_vector(normal)
+            // } else {
+            //     unit_vector(normal)
+            // };
+
+            hit_record.set_face_normal(ray, outward_normal);
+
+            hit_record.material = self.material.clone();
+            // To do: ☠☠ once you have those coordinates, you can reverse based on them, like in Parallelogram ☠☠
+            hit_record.surface_coords = surface_coords;
+            hit_record.dp_du = Vector3::default();
+            hit_record.dp_dv = Vector3::default();
+
+            return true;
         }
 
-        let p: Point3 = ray.at(root);
-
-        // This was also obtained with scripts/sympy_quadric.py
-        let normal: Vector3 = Vector3 {
-            x: 2.0 * self.p1.x * p.x + self.p2.x * p.y + self.p2.y * p.z,
-            y: 2.0 * self.p1.y * p.y + self.p2.x * p.x + self.p2.z * p.z,
-            z: 2.0 * self.p1.z * p.z + self.p2.y * p.x + self.p2.z * p.y,
-        } + self.p3;
-
-        hit_record.t = root;
-        hit_record.p = p;
-        // Make the normal unit lenght
-        let outward_normal: Point3 = unit_vector(normal);
-        // Leave this here to remember, this is already done in set face normal
-        // If the ray originates inside of the surface, reverse the normal
-        // let outward_normal: Point3 = if dot(&d, &p) > 0.0 {
-        //     -unit_vector(normal)
-        // } else {
-        //     unit_vector(normal)
-        // };
-
-        hit_record.set_face_normal(ray, outward_normal);
-
-        hit_record.material = self.material.clone();
-        // To do: Since there is no general closed form coordinates, find some other way to get surface coordinates. Use differential geomety?
-        // To do: ☠☠ once you have those coordinates, you can reverse based on them, like in Parallelogram ☠☠
-        hit_record.surface_coords = SurfaceCoordinate { u: 0.0, v: 0.0 };
-
-        true
+        false
     }
     fn bounding_box(&self) -> &AABB {
         &self.bounding_box
@@ -3,9 +3,9 @@ use std::ops::Range;
 use std::sync::Arc;
 
 use crate::aabb::AABB;
-use crate::hittable::{HitRecord, Hittable, SurfaceCoordinate};
+use crate::hittable::{HitRecord, Hittable, SurfaceCoordinate, is_cut_out};
 use crate::material::Material;
-use crate::point3::Point3;
+use crate::point3::{Point3, Vector3};
 use crate::ray::Ray;
 
 /// An sphere hittable (you know the one, round etc). Constructed with sphere
@@ -52,27 +52,31 @@ impl Hittable for Sphere {
         }
         let sqrt_discriminant: f64 = f64::sqrt(discriminant);
 
-        // Find the nearest root that lies in the acceptable range
-        let mut root: f64 = (h - sqrt_discriminant) / a;
-
-        if !ray_t.contains(&root) {
-            root = (h + sqrt_discriminant) / a;
+        // Find the nearest root that lies in the acceptable range. If the sphere is cut out there, try the far one
+        for root in [(h - sqrt_discriminant) / a, (h + sqrt_discriminant) / a] {
             if !ray_t.contains(&root) {
-                return false;
+                continue;
             }
-        }
 
-        hit_record.t = root;
-        hit_record.p = ray.at(hit_record.t);
+            let p: Point3 = ray.at(root);
+            let outward_normal: Point3 = (p - self.center) / self.radius;
+            let surface_coords: SurfaceCoordinate = get_sphere_uv(&outward_normal);
 
-        let outward_normal: Point3 = (ray.at(root) - self.center) / self.radius;
-        hit_record.set_face_normal(ray, outward_normal);
+            if is_cut_out(&*self.material, surface_coords, &p) {
+                continue;
+            }
 
-        hit_record.material = self.material.clone();
+            hit_record.t = root;
+            hit_record.p = p;
+            hit_record.set_face_normal(ray, outward_normal);
+            hit_record.material = self.material.clone();
+            hit_record.surface_coords = surface_coords;
+            (hit_record.dp_du, hit_record.dp_dv) = sphere_derivatives(&outward_normal, self.radius);
 
-        hit_record.surface_coords = get_sphere_uv(&outward_normal);
+            return true;
+        }
 
-        true
+        false
     }
     fn bounding_box(&self) -> &AABB {
         &self.bounding_box
@@ -96,3 +100,18 @@ pub fn get_sphere_uv(p: &Point3) -> SurfaceCoordinate {
         v: theta / PI,
     }
 }
+
+/// Derivatives of a point on the sphere with respect to the coordinates given by get_sphere_uv, from its unit
+/// outward normal n. At the poles they are zero
+pub fn sphere_derivatives(n: &Vector3, radius: f64) -> (Vector3, Vector3) {
+    let sin_theta: f64 = (1.0 - n.y * n.y).max(0.0).sqrt();
+    if sin_theta < 1e-8 {
+        return (Vector3::default(), Vector3::default());
+    }
+
+    let dp_du: Vector3 = 2.0 * PI * radius * Vector3::new(n.z, 0.0, -n.x);
+    let dp_dv: Vector3 =
+        PI * radius * Vector3::new(-n.y * n.x / sin_theta, sin_theta, -n.y * n.z / sin_theta);
+
+    (dp_du, dp_dv)
+}
@@ -2,7 +2,7 @@ use std::ops::Range;
 use std::sync::Arc;
 
 use crate::aabb::{AABB, join_aabbs};
-use crate::hittable::{HitRecord, Hittable, SurfaceCoordinate};
+use crate::hittable::{HitRecord, Hittable, SurfaceCoordinate, is_cut_out};
 use crate::material::Material;
 use crate::point3::{Point3, Vector3, cross, dot, unit_vector};
 use crate::ray::Ray;
@@ -25,6 +25,12 @@ pub struct Triangle {
     normal: Vector3,
     /// The constant of the plane defined by the vectors
     d: f64,
+    /// Texture coordinates of the three vertices, if the triangle comes from a mesh that has them.
+    /// Otherwise the barycentric coordinates are used
+    texture_coords: Option<[SurfaceCoordinate; 3]>,
+    /// Derivatives of the point with respect to the texture coordinates
+    dp_du: Vector3,
+    dp_dv: Vector3,
 }
 
 fn create_aabb_para(q: Point3, u: Point3, v: Point3) -> AABB {
@@ -53,6 +59,9 @@ impl Triangle {
             bounding_box,
             normal,
             d,
+            texture_coords: None,
+            dp_du: u,
+            dp_dv: v,
         }
     }
     pub fn from_vertex_locations(
@@ -83,6 +92,9 @@ impl Triangle {
             bounding_box,
             normal,
             d,
+            texture_coords: None,
+            dp_du: u,
+            dp_dv: v,
         }
     }
     pub fn from_vertex_locations_with_normal(
@@ -111,10 +123,31 @@ impl Triangle {
             bounding_box,
             normal,
             d,
+            texture_coords: None,
+            dp_du: u,
+            dp_dv: v,
         }
     }
 }
 
+impl Triangle {
+    /// Give texture coordinates to the vertices, in the same order as the vertex locations
+    pub fn with_texture_coordinates(mut self, coords: [SurfaceCoordinate; 3]) -> Triangle {
+        let (du1, dv1): (f64, f64) = (coords[1].u - coords[0].u, coords[1].v - coords[0].v);
+        let (du2, dv2): (f64, f64) = (coords[2].u - coords[0].u, coords[2].v - coords[0].v);
+        let determinant: f64 = du1 * dv2 - dv1 * du2;
+
+        // Solve u = du1 dp/du + dv1 dp/dv and v = du2 dp/du + dv2 dp/dv. Degenerate coordinates keep the
+        // derivatives of the barycentric coordinates
+        if determinant.abs() > 1e-12 {
+            self.dp_du = (dv2 * self.u - dv1 * self.v) / determinant;
+            self.dp_dv = (du1 * self.v - du2 * self.u) / determinant;
+        }
+        self.texture_coords = Some(coords);
+        self
+    }
+}
+
 impl Hittable for Triangle {
     /// Ray-triangle intersection will be determined in three steps:
     ///     1. Finding the plane Ax + By + Cz = d that contains that triangle,
@@ -143,13 +176,29 @@ impl Hittable for Triangle {
             return false;
         }
 
-        let surface_coords: SurfaceCoordinate = SurfaceCoordinate { u: alpha, v: beta };
+        let surface_coords: SurfaceCoordinate = match &self.texture_coords {
+            Some([c0, c1, c2]) => {
+                let gamma: f64 = 1.0 - alpha - beta;
+                SurfaceCoordinate {
+                    u: gamma * c0.u + alpha * c1.u + beta * c2.u,
+                    v: gamma * c0.v + alpha * c1.v + beta * c2.v,
+                }
+            }
+            None => SurfaceCoordinate { u: alpha, v: beta },
+        };
+        // Cut out parts let the ray through, so the search continues with the surfaces behind
+        if is_cut_out(&*self.material, surface_coords, &intersection) {
+            return false;
+        }
+
         hit_record.surface_coords = surface_coords;
 
         hit_record.t = t;
         hit_record.p = intersection;
         hit_record.material = self.material.clone();
         hit_record.set_face_normal(ray, self.normal);
+        hit_record.dp_du = self.dp_du;
+        hit_record.dp_dv = self.dp_dv;
 
         true
     }
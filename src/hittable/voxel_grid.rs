@@ -0,0 +1,242 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::Arc;
+
+use crate::hittable::heterogeneous_medium::{DensityField, HeterogeneousMedium};
+use crate::hittable::hittable_list::HittableSlice;
+use crate::hittable::parallelogram::create_box;
+use crate::hittable::{Hittable, RotateY, Translate};
+use crate::material::Lambertian;
+use crate::perlin::trilinear_interp;
+use crate::point3::color::Color;
+use crate::point3::{Point3, Vector3, rotate_y};
+
+/// Values on a regular 3D grid, like the density or the temperature of a smoke simulation. Each value is the
+/// center of a voxel, and x changes the fastest in values, then y and then z
+pub struct VoxelGrid {
+    pub dimensions: [usize; 3],
+    values: Vec<f64>,
+    /// Biggest of the values, used as the majorant of media
+    max_value: f64,
+}
+
+impl VoxelGrid {
+    pub fn new(dimensions: [usize; 3], values: Vec<f64>) -> VoxelGrid {
+        if dimensions.contains(&0) {
+            panic!(
+                "a grid needs at least one voxel along each axis, got {}x{}x{}",
+                dimensions[0], dimensions[1], dimensions[2]
+            );
+        }
+        if values.len() != dimensions[0] * dimensions[1] * dimensions[2] {
+            panic!(
+                "a grid of {}x{}x{} needs {} values, got {}",
+                dimensions[0],
+                dimensions[1],
+                dimensions[2],
+                dimensions[0] * dimensions[1] * dimensions[2],
+                values.len()
+            );
+        }
+        let max_value: f64 = values.iter().copied().fold(0.0, f64::max);
+
+        VoxelGrid {
+            dimensions,
+            values,
+            max_value,
+        }
+    }
+
+    /// Load a grid from a text file. The first line has the dimensions (like "64 128 64") and the rest the values,
+    /// separated by whitespace, with x changing the fastest. Lines starting with # are comments
+    pub fn load_text(path: &str) -> VoxelGrid {
+        let file: File = File::open(path)
+            .unwrap_or_else(|error| panic!("could not open the voxel grid {path}: {error}"));
+        let mut numbers = BufReader::new(file)
+            .lines()
+            .map(|line| {
+                line.unwrap_or_else(|error| panic!("could not read the voxel grid {path}: {error}"))
+            })
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(|line| {
+                line.split_ascii_whitespace()
+                    .map(str::to_owned)
+                    .collect::<Vec<String>>()
+            });
+
+        let mut dimension = || -> usize {
+            numbers
+                .next()
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or_else(|| {
+                    panic!("the voxel grid {path} must start with its three dimensions")
+                })
+        };
+        let dimensions: [usize; 3] = [dimension(), dimension(), dimension()];
+
+        let values: Vec<f64> = numbers
+            .map(|s| {
+                s.parse::<f64>().unwrap_or_else(|_| {
+                    panic!("the voxel grid {path} has a value that is not a number: {s:?}")
+                })
+            })
+            .collect();
+
+        VoxelGrid::new(dimensions, values)
+    }
+
+    /// Load a grid from a binary file: the dimensions as three little endian u32, followed by the values as
+    /// little endian f32, with x changing the fastest
+    pub fn load_raw(path: &str) -> VoxelGrid {
+        let mut bytes: Vec<u8> = Vec::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_end(&mut bytes))
+            .unwrap_or_else(|error| panic!("could not read the voxel grid {path}: {error}"));
+
+        if bytes.len() < 12 {
+            panic!("the voxel grid {path} must start with its three dimensions");
+        }
+        let (header, data) = bytes.split_at(12);
+        let dimensions: [usize; 3] = [0, 1, 2]
+            .map(|i| u32::from_le_bytes(header[4 * i..4 * i + 4].try_into().unwrap()) as usize);
+
+        let values: Vec<f64> = data
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()) as f64)
+            .collect();
+
+        VoxelGrid::new(dimensions, values)
+    }
+
+    pub fn max_value(&self) -> f64 {
+        self.max_value
+    }
+
+    fn at(&self, i: usize, j: usize, k: usize) -> f64 {
+        self.values[i + self.dimensions[0] * (j + self.dimensions[1] * k)]
+    }
+
+    /// Trilinear interpolation of the values at local, which goes from 0 to 1 along each side of the grid.
+    /// Zero outside of it
+    pub fn interpolate(&self, local: &Point3) -> f64 {
+        if !(0.0..=1.0).contains(&local.x)
+            || !(0.0..=1.0).contains(&local.y)
+            || !(0.0..=1.0).contains(&local.z)
+        {
+            return 0.0;
+        }
+
+        // Position in voxels, relative to the centers, and the voxel before it along an axis
+        let axis = |coordinate: f64, size: usize| -> (usize, usize, f64) {
+            let position: f64 = (coordinate * size as f64 - 0.5).clamp(0.0, (size - 1) as f64);
+            let before: usize = position.floor() as usize;
+            let after: usize = (before + 1).min(size - 1);
+
+            (before, after, position - before as f64)
+        };
+        let (i0, i1, u): (usize, usize, f64) = axis(local.x, self.dimensions[0]);
+        let (j0, j1, v): (usize, usize, f64) = axis(local.y, self.dimensions[1]);
+        let (k0, k1, w): (usize, usize, f64) = axis(local.z, self.dimensions[2]);
+
+        let c: [[[f64; 2]; 2]; 2] = [
+            [
+                [self.at(i0, j0, k0), self.at(i0, j0, k1)],
+                [self.at(i0, j1, k0), self.at(i0, j1, k1)],
+            ],
+            [
+                [self.at(i1, j0, k0), self.at(i1, j0, k1)],
+                [self.at(i1, j1, k0), self.at(i1, j1, k1)],
+            ],
+        ];
+
+        trilinear_interp(c, u, v, w)
+    }
+}
+
+/// Where a grid is in the scene: a box with a corner at position and the given size, rotated around the vertical
+/// axis that goes through that corner
+#[derive(Clone, Copy)]
+pub struct GridPlacement {
+    pub position: Point3,
+    pub size: Vector3,
+    sin_theta: f64,
+    cos_theta: f64,
+    angle_in_degrees: f64,
+}
+
+impl GridPlacement {
+    pub fn new(position: Point3, size: Vector3, angle_in_degrees: f64) -> GridPlacement {
+        let radians: f64 = angle_in_degrees.to_radians();
+
+        GridPlacement {
+            position,
+            size,
+            sin_theta: radians.sin(),
+            cos_theta: radians.cos(),
+            angle_in_degrees,
+        }
+    }
+
+    /// Coordinates of p inside the box, from 0 to 1 along each side
+    pub fn to_local(&self, p: &Point3) -> Point3 {
+        // The same transform as RotateY, which turns world space into object space
+        let q: Point3 = rotate_y(&(*p - self.position), self.cos_theta, self.sin_theta);
+
+        Point3::new(q.x / self.size.x, q.y / self.size.y, q.z / self.size.z)
+    }
+
+    /// The box, for bounding media
+    pub fn boundary(&self) -> Arc<dyn Hittable> {
+        // The material is never used, as media only look for the points where rays go in and out
+        let sides: Arc<HittableSlice> = Arc::new(HittableSlice::from_hittable_list(create_box(
+            Point3::default(),
+            self.size,
+            Lambertian::from_color(Color::white()),
+        )));
+        let rotated: Arc<RotateY> = Arc::new(RotateY::new(sides, self.angle_in_degrees));
+
+        Arc::new(Translate::new(rotated, self.position))
+    }
+}
+
+/// A grid placed in the scene, with its values multiplied by scale
+pub struct GridField {
+    pub grid: Arc<VoxelGrid>,
+    pub placement: GridPlacement,
+    pub scale: f64,
+}
+
+impl GridField {
+    pub fn new(grid: Arc<VoxelGrid>, placement: GridPlacement, scale: f64) -> GridField {
+        GridField {
+            grid,
+            placement,
+            scale,
+        }
+    }
+}
+
+impl DensityField for GridField {
+    fn density(&self, p: &Point3) -> f64 {
+        self.scale * self.grid.interpolate(&self.placement.to_local(p))
+    }
+
+    fn max_density(&self) -> f64 {
+        self.scale * self.grid.max_value()
+    }
+}
+
+/// A medium with the density of grid (times density_scale) that fills the box of placement. For smoke or fire that
+/// glows, give it a BlackBodyMedium as its phase function
+pub fn grid_medium(
+    grid: Arc<VoxelGrid>,
+    density_scale: f64,
+    placement: GridPlacement,
+    color: Color,
+) -> HeterogeneousMedium {
+    HeterogeneousMedium::from_color(
+        placement.boundary(),
+        Arc::new(GridField::new(grid, placement, density_scale)),
+        color,
+    )
+}
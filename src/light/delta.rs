@@ -0,0 +1,152 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::light::{Light, LightSample};
+use crate::point3::color::Color;
+use crate::point3::{Point3, Vector3, dot, unit_vector};
+
+/// Squared distance below which a point counts as being at the position of a light. There the direction towards
+/// the light is not defined and the inverse square law gives infinite light, so the light does not reach it
+const MIN_DISTANCE_SQUARED: f64 = 1e-12;
+
+/// A light that shines equally in all directions from a single point
+pub struct PointLight {
+    position: Point3,
+    /// Radiant intensity: power per unit solid angle
+    intensity: Color,
+}
+
+impl PointLight {
+    /// Create a point light that emits a total power (in watts) with the given color
+    pub fn new(position: Point3, color: Color, power: f64) -> Arc<PointLight> {
+        Arc::new(PointLight {
+            position,
+            intensity: color * (power / (4.0 * PI)),
+        })
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: &Point3, light_sample: &mut LightSample) -> bool {
+        let to_light: Vector3 = self.position - *p;
+        let distance_squared: f64 = to_light.length_squared();
+        if distance_squared < MIN_DISTANCE_SQUARED {
+            return false;
+        }
+
+        light_sample.distance = distance_squared.sqrt();
+        light_sample.direction = to_light / light_sample.distance;
+        // Inverse square law
+        light_sample.radiance = self.intensity / distance_squared;
+        light_sample.pdf = 1.0;
+
+        true
+    }
+}
+
+/// A point light that only shines inside a cone. The light is at full intensity inside the inner angle
+/// and fades smoothly to zero at the outer angle
+pub struct SpotLight {
+    position: Point3,
+    /// Unit vector along the axis of the cone
+    axis: Vector3,
+    /// Radiant intensity along the axis
+    intensity: Color,
+    /// Cosine of the angle where the fall off starts
+    cos_inner: f64,
+    /// Cosine of the angle where the light stops
+    cos_outer: f64,
+}
+
+impl SpotLight {
+    /// Create a spot light at position pointing at look_at. The angles are measured from the axis of the cone, in degrees.
+    /// The power (in watts) is the total emitted power, so widening the cone dims the light
+    pub fn new(
+        position: Point3,
+        look_at: Point3,
+        color: Color,
+        power: f64,
+        inner_angle: f64,
+        outer_angle: f64,
+    ) -> Arc<SpotLight> {
+        let cos_inner: f64 = inner_angle.to_radians().cos();
+        let cos_outer: f64 = outer_angle.max(inner_angle).to_radians().cos();
+
+        // Solid angle of the cone, counting the fall off region as half lit
+        let solid_angle: f64 = 2.0 * PI * (1.0 - 0.5 * (cos_inner + cos_outer));
+
+        Arc::new(SpotLight {
+            position,
+            axis: unit_vector(look_at - position),
+            intensity: color * (power / solid_angle),
+            cos_inner,
+            cos_outer,
+        })
+    }
+
+    /// Fraction of the intensity emitted in a direction whose angle with the axis has cosine cos_theta
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_inner {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_outer {
+            return 0.0;
+        }
+        // Smoothstep between the two cones
+        let t: f64 = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: &Point3, light_sample: &mut LightSample) -> bool {
+        let to_light: Vector3 = self.position - *p;
+        let distance_squared: f64 = to_light.length_squared();
+        if distance_squared < MIN_DISTANCE_SQUARED {
+            return false;
+        }
+        let distance: f64 = distance_squared.sqrt();
+        let direction: Vector3 = to_light / distance;
+
+        let falloff: f64 = self.falloff(dot(&(-direction), &self.axis));
+        if falloff <= 0.0 {
+            return false;
+        }
+
+        light_sample.distance = distance;
+        light_sample.direction = direction;
+        light_sample.radiance = self.intensity * (falloff / distance_squared);
+        light_sample.pdf = 1.0;
+
+        true
+    }
+}
+
+/// A light infinitely far away, so that all of its rays are parallel, like an idealized sun
+pub struct DirectionalLight {
+    /// Unit vector pointing towards the light (opposite to the direction the light travels in)
+    to_light: Vector3,
+    /// Irradiance on a surface perpendicular to the light, in W/m²
+    irradiance: Color,
+}
+
+impl DirectionalLight {
+    /// Create a light that travels along direction, giving irradiance (in W/m²) to surfaces facing it
+    pub fn new(direction: Vector3, color: Color, irradiance: f64) -> Arc<DirectionalLight> {
+        Arc::new(DirectionalLight {
+            to_light: -unit_vector(direction),
+            irradiance: color * irradiance,
+        })
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: &Point3, light_sample: &mut LightSample) -> bool {
+        light_sample.distance = f64::INFINITY;
+        light_sample.direction = self.to_light;
+        light_sample.radiance = self.irradiance;
+        light_sample.pdf = 1.0;
+
+        true
+    }
+}
@@ -0,0 +1,117 @@
+/// A piecewise-constant distribution over [0, 1). The function is given as n non negative values, each covering
+/// an interval of width 1/n, and samples are drawn proportionally to those values
+pub struct Distribution1D {
+    function: Vec<f64>,
+    /// Cumulative distribution function, with n + 1 entries going from 0.0 to 1.0
+    cdf: Vec<f64>,
+    /// Integral of the function over [0, 1)
+    integral: f64,
+}
+
+impl Distribution1D {
+    pub fn new(function: Vec<f64>) -> Distribution1D {
+        let n: usize = function.len();
+        // If everything is zero there is nothing to prefer, so fall back to a uniform distribution
+        let function: Vec<f64> = if function.iter().all(|f| *f <= 0.0) {
+            vec![1.0; n]
+        } else {
+            function.into_iter().map(|f| f.max(0.0)).collect()
+        };
+
+        let mut cdf: Vec<f64> = vec![0.0; n + 1];
+        for i in 1..=n {
+            cdf[i] = cdf[i - 1] + function[i - 1] / n as f64;
+        }
+
+        let integral: f64 = cdf[n];
+        for c in cdf.iter_mut() {
+            *c /= integral;
+        }
+
+        Distribution1D {
+            function,
+            cdf,
+            integral,
+        }
+    }
+
+    /// Number of intervals of the distribution
+    pub fn count(&self) -> usize {
+        self.function.len()
+    }
+
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Transform a uniform random number in [0, 1) into a sample of the distribution.
+    /// Returns the sample, its probability density and the index of the interval it falls in
+    pub fn sample_continuous(&self, random: f64) -> (f64, f64, usize) {
+        // Find the last entry of the cdf that is less or equal than the random number
+        let offset: usize =
+            (self.cdf.partition_point(|c| *c <= random).max(1) - 1).min(self.count() - 1);
+
+        let width: f64 = self.cdf[offset + 1] - self.cdf[offset];
+        let du: f64 = if width > 0.0 {
+            (random - self.cdf[offset]) / width
+        } else {
+            0.0
+        };
+
+        let x: f64 = (offset as f64 + du) / self.count() as f64;
+        (x, self.function[offset] / self.integral, offset)
+    }
+
+    /// Probability density of the distribution at x € [0, 1)
+    pub fn pdf(&self, x: f64) -> f64 {
+        let offset: usize = ((x * self.count() as f64) as usize).min(self.count() - 1);
+        self.function[offset] / self.integral
+    }
+}
+
+/// A piecewise-constant distribution over [0, 1) x [0, 1), built from a grid of values stored row by row.
+/// The first coordinate is sampled from the row's conditional distribution and the second from the marginal distribution of the rows
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(function: &[f64], width: usize, height: usize) -> Distribution2D {
+        let conditional: Vec<Distribution1D> = function
+            .chunks(width)
+            .take(height)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+
+        // Use the integrals of the original rows, since the conditional distributions of black rows fall back to uniform
+        let marginal: Distribution1D = Distribution1D::new(
+            function
+                .chunks(width)
+                .take(height)
+                .map(|row| row.iter().map(|f| f.max(0.0)).sum::<f64>() / width as f64)
+                .collect(),
+        );
+
+        Distribution2D {
+            conditional,
+            marginal,
+        }
+    }
+
+    /// Transform two uniform random numbers in [0, 1) into a sample (s, t) of the distribution and its probability density
+    pub fn sample_continuous(&self, random_s: f64, random_t: f64) -> (f64, f64, f64) {
+        let (t, pdf_t, row) = self.marginal.sample_continuous(random_t);
+        let (s, pdf_s, _) = self.conditional[row].sample_continuous(random_s);
+
+        (s, t, pdf_s * pdf_t)
+    }
+
+    /// Probability density of the distribution at (s, t)
+    pub fn pdf(&self, s: f64, t: f64) -> f64 {
+        let row: usize =
+            ((t * self.marginal.count() as f64) as usize).min(self.marginal.count() - 1);
+
+        self.conditional[row].pdf(s) * self.marginal.pdf(t)
+    }
+}
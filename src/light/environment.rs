@@ -0,0 +1,155 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use image::Rgb32FImage;
+
+use crate::hittable::SurfaceCoordinate;
+use crate::hittable::sphere::get_sphere_uv;
+use crate::light::LightSample;
+use crate::light::distribution::Distribution2D;
+use crate::point3::color::Color;
+use crate::point3::{Point3, Vector3, unit_vector};
+use crate::texture::{ColorSpace, load_linear_image};
+
+/// What a ray sees when it escapes the scene. The environment is infinitely far away, so it only depends on the direction of the ray.
+/// The default for sample is false (the environment can not be sampled directly) and for pdf 0.0
+pub trait Environment: Sync + Send {
+    /// Radiance arriving from the environment along direction, which does not need to be unit length
+    fn value(&self, direction: &Vector3) -> Color;
+
+    /// Choose a direction towards the environment, preferably towards its brightest parts.
+    /// Returns false if the environment is not worth sampling, for example because it is constant
+    fn sample(&self, _light_sample: &mut LightSample) -> bool {
+        false
+    }
+
+    /// Probability density (per unit solid angle) with which sample would produce direction
+    fn pdf(&self, _direction: &Vector3) -> f64 {
+        0.0
+    }
+}
+
+/// The same color in every direction. This is the old background color of the camera
+pub struct UniformEnvironment {
+    color: Color,
+}
+
+impl UniformEnvironment {
+    pub fn new(color: Color) -> Arc<UniformEnvironment> {
+        Arc::new(UniformEnvironment { color })
+    }
+}
+
+impl Environment for UniformEnvironment {
+    fn value(&self, _direction: &Vector3) -> Color {
+        self.color
+    }
+}
+
+/// An environment light from an equirectangular (latitude-longitude) image, usually a .hdr or .exr file.
+/// Directions are importance sampled proportionally to the luminance of the pixels, so small and bright features
+/// like the sun are found by shadow rays instead of by chance. Create with new_or_fallback()
+pub struct ImageEnvironment {
+    image: Rgb32FImage,
+    /// Multiplies the values of the image
+    intensity: f64,
+    /// Distribution over the pixels of the image, with s going along the columns and t along the rows (from the top)
+    distribution: Distribution2D,
+}
+
+impl ImageEnvironment {
+    /// Load the image in path. If the path gives an error, fall back to a uniform environment that is easy to see
+    pub fn new_or_fallback(path: &str, intensity: f64) -> Arc<dyn Environment> {
+        match load_linear_image(path, ColorSpace::Srgb) {
+            Ok(image) => Arc::new(ImageEnvironment::new(image, intensity)),
+            Err(image_error) => {
+                eprintln!("Could not load the environment image. Falling back to default. Error:");
+                eprintln!("{}", image_error);
+
+                UniformEnvironment::new(Color::new(1.0, 0.0, 0.862745098039))
+            }
+        }
+    }
+
+    pub fn new(image: Rgb32FImage, intensity: f64) -> ImageEnvironment {
+        let width: u32 = image.width();
+        let height: u32 = image.height();
+
+        // Rows near the poles cover less solid angle, so weight their brightness by sin(theta)
+        let mut function: Vec<f64> = Vec::with_capacity((width * height) as usize);
+        for j in 0..height {
+            let sin_theta: f64 = (PI * (j as f64 + 0.5) / height as f64).sin();
+            for i in 0..width {
+                function.push(pixel_to_color(image.get_pixel(i, j)).luminance() * sin_theta);
+            }
+        }
+
+        ImageEnvironment {
+            distribution: Distribution2D::new(&function, width as usize, height as usize),
+            image,
+            intensity,
+        }
+    }
+
+    /// Returns the color of the pixel containing the image coordinates (s, t), with t going from the top of the image
+    fn lookup(&self, s: f64, t: f64) -> Color {
+        let i: u32 = ((s * self.image.width() as f64) as u32).min(self.image.width() - 1);
+        let j: u32 = ((t * self.image.height() as f64) as u32).min(self.image.height() - 1);
+
+        self.intensity * pixel_to_color(self.image.get_pixel(i, j))
+    }
+}
+
+impl Environment for ImageEnvironment {
+    fn value(&self, direction: &Vector3) -> Color {
+        // Use the same mapping as the sphere, so an environment looks like a texture on a huge sphere seen from inside
+        let surface_coords: SurfaceCoordinate = get_sphere_uv(&unit_vector(*direction));
+
+        self.lookup(surface_coords.u, 1.0 - surface_coords.v)
+    }
+
+    fn sample(&self, light_sample: &mut LightSample) -> bool {
+        let (s, t, map_pdf) = self
+            .distribution
+            .sample_continuous(rand::random_range(0.0..1.0), rand::random_range(0.0..1.0));
+
+        if map_pdf == 0.0 {
+            return false;
+        }
+
+        // Invert get_sphere_uv: theta goes from the bottom (v = 0) and phi starts at X = -1
+        let theta: f64 = PI * (1.0 - t);
+        let phi: f64 = 2.0 * PI * s;
+        let sin_theta: f64 = theta.sin();
+
+        if sin_theta <= 0.0 {
+            return false;
+        }
+
+        light_sample.direction =
+            Point3::new(-sin_theta * phi.cos(), -theta.cos(), sin_theta * phi.sin());
+        light_sample.distance = f64::INFINITY;
+        light_sample.radiance = self.lookup(s, t);
+        // Change of variables from the image to the sphere of directions
+        light_sample.pdf = map_pdf / (2.0 * PI * PI * sin_theta);
+
+        true
+    }
+
+    fn pdf(&self, direction: &Vector3) -> f64 {
+        let surface_coords: SurfaceCoordinate = get_sphere_uv(&unit_vector(*direction));
+        let sin_theta: f64 = (PI * surface_coords.v).sin();
+
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        self.distribution
+            .pdf(surface_coords.u, 1.0 - surface_coords.v)
+            / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+fn pixel_to_color(pixel: &image::Rgb<f32>) -> Color {
+    Color::new(pixel.0[0] as f64, pixel.0[1] as f64, pixel.0[2] as f64)
+}
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use crate::light::environment::{Environment, UniformEnvironment};
+use crate::point3::{Point3, Vector3, color::Color};
+
+/// A direction towards a light chosen by the light itself, so that the integrator can shoot a shadow ray
+/// in that direction instead of waiting for a random bounce to find the light
+#[derive(Default)]
+pub struct LightSample {
+    /// Unit vector pointing from the shaded point towards the light
+    pub direction: Vector3,
+    /// Distance to the light along direction. Infinite for lights that are infinitely far away
+    pub distance: f64,
+    /// Radiance arriving at the shaded point along direction. For delta lights this is the irradiance they produce
+    /// on a surface perpendicular to direction
+    pub radiance: Color,
+    /// Probability density (per unit solid angle) of having chosen direction. For delta lights, which can only be
+    /// reached by sampling them, this is 1.0
+    pub pdf: f64,
+}
+
+/// A delta light: a light with no area, which rays scattered by materials can never hit. The only way it can
+/// contribute to the image is through shadow rays shot towards it by the integrator
+pub trait Light: Sync + Send {
+    /// Fill light_sample with the direction from p towards the light and the light arriving at p.
+    /// Returns false if the light does not reach p at all, for example outside the cone of a spot light
+    fn sample(&self, p: &Point3, light_sample: &mut LightSample) -> bool;
+}
+
+/// Everything in a scene that gives light, apart from emissive materials
+pub struct Lighting {
+    /// What the rays that escape the scene see
+    pub environment: Arc<dyn Environment>,
+    pub lights: Vec<Arc<dyn Light>>,
+}
+
+impl Lighting {
+    /// A constant background color and no delta lights, like in the books
+    pub fn from_background_color(background_color: Color) -> Lighting {
+        Lighting {
+            environment: UniformEnvironment::new(background_color),
+            lights: Vec::new(),
+        }
+    }
+}
+
+/// Power heuristic (with exponent 2) for multiple importance sampling. Returns the weight of a sample
+/// taken with the strategy of density pdf_f when the same direction could also have come from the strategy of density pdf_g
+pub fn power_heuristic(pdf_f: f64, pdf_g: f64) -> f64 {
+    let f: f64 = pdf_f * pdf_f;
+    let g: f64 = pdf_g * pdf_g;
+
+    if f + g == 0.0 {
+        return 0.0;
+    }
+    f / (f + g)
+}
+
+pub mod delta;
+pub mod distribution;
+pub mod environment;
+pub mod sky;
@@ -0,0 +1,252 @@
+// A practical analytic model for daylight, Preetham, Shirley and Smits (1999): https://courses.cs.duke.edu/cps124/fall01/resources/p91-preetham.pdf
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::light::LightSample;
+use crate::light::environment::Environment;
+use crate::point3::color::{Color, xyz_to_rgb};
+use crate::point3::{Point3, Vector3, dot, orthonormal_basis, random_unit_vector, unit_vector};
+
+/// The luminances of the model are in kcd/m². This brings a clear day to the 0..1 range used by the rest of the renderer
+const SKY_SCALE: f64 = 1.0 / 30.0;
+/// Illuminance of the sun at the top of the atmosphere, in klx
+const SUN_ILLUMINANCE: f64 = 128.0;
+/// Angular radius of the sun as seen from the earth
+pub const SUN_ANGULAR_RADIUS_DEGREES: f64 = 0.2665;
+/// Probability of sampling the sun disk instead of the sky when the sun is above the horizon
+const SUN_SAMPLING_PROBABILITY: f64 = 0.5;
+
+/// Coefficients of the Perez formula for the distribution of luminance over the sky
+struct Perez {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+}
+
+impl Perez {
+    /// theta is the angle between the view direction and the zenith and gamma the angle between the view direction and the sun
+    fn evaluate(&self, theta: f64, gamma: f64) -> f64 {
+        let cos_gamma: f64 = gamma.cos();
+        (1.0 + self.a * (self.b / theta.cos().max(0.01)).exp())
+            * (1.0 + self.c * (self.d * gamma).exp() + self.e * cos_gamma * cos_gamma)
+    }
+}
+
+/// A procedural daylight sky (Preetham model) together with the sun, a disk of finite angular size.
+/// The sky covers the upper half of the directions (y > 0). The sun is importance sampled, so it behaves as a
+/// directional light with soft shadows. Create with new()
+pub struct PhysicalSky {
+    /// Unit vector pointing towards the center of the sun
+    sun_direction: Vector3,
+    /// Cosine of the angular radius of the sun
+    sun_cos_radius: f64,
+    /// Radiance of the sun disk, already attenuated by the atmosphere
+    sun_radiance: Color,
+    /// Angle between the sun and the zenith
+    theta_sun: f64,
+    /// Luminance and chromaticity of the zenith, in xyY
+    zenith: Point3,
+    perez_luminance: Perez,
+    perez_x: Perez,
+    perez_y: Perez,
+}
+
+impl PhysicalSky {
+    /// Create a sky for a sun that is sun_elevation degrees above the horizon and sun_azimuth degrees around the
+    /// y axis, measured from the x axis towards the z axis. Turbidity goes from 2 (very clear) to around 10 (hazy).
+    /// The model is only valid while the sun is above the horizon
+    pub fn new(sun_elevation: f64, sun_azimuth: f64, turbidity: f64) -> Arc<PhysicalSky> {
+        let elevation: f64 = sun_elevation.to_radians();
+        let azimuth: f64 = sun_azimuth.to_radians();
+        let sun_direction: Vector3 = Point3::new(
+            elevation.cos() * azimuth.cos(),
+            elevation.sin(),
+            elevation.cos() * azimuth.sin(),
+        );
+
+        let theta_sun: f64 = PI * 0.5 - elevation;
+        let t: f64 = turbidity;
+
+        // Zenith luminance and chromaticity, fitted by Preetham et al.
+        let chi: f64 = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let zenith_luminance: f64 = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let theta_powers: [f64; 4] = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.0];
+        let polynomial = |coefficients: [f64; 4]| -> f64 {
+            coefficients
+                .iter()
+                .zip(theta_powers.iter())
+                .map(|(c, p)| c * p)
+                .sum()
+        };
+        let zenith_x: f64 = t * t * polynomial([0.00166, -0.00375, 0.00209, 0.0])
+            + t * polynomial([-0.02903, 0.06377, -0.03202, 0.00394])
+            + polynomial([0.11693, -0.21196, 0.06052, 0.25886]);
+        let zenith_y: f64 = t * t * polynomial([0.00275, -0.00610, 0.00317, 0.0])
+            + t * polynomial([-0.04214, 0.08970, -0.04153, 0.00516])
+            + polynomial([0.15346, -0.26756, 0.06670, 0.26688]);
+
+        let perez_luminance: Perez = Perez {
+            a: 0.1787 * t - 1.4630,
+            b: -0.3554 * t + 0.4275,
+            c: -0.0227 * t + 5.3251,
+            d: 0.1206 * t - 2.5771,
+            e: -0.0670 * t + 0.3703,
+        };
+        let perez_x: Perez = Perez {
+            a: -0.0193 * t - 0.2592,
+            b: -0.0665 * t + 0.0008,
+            c: -0.0004 * t + 0.2125,
+            d: -0.0641 * t - 0.8989,
+            e: -0.0033 * t + 0.0452,
+        };
+        let perez_y: Perez = Perez {
+            a: -0.0167 * t - 0.2608,
+            b: -0.0950 * t + 0.0092,
+            c: -0.0079 * t + 0.2102,
+            d: -0.0441 * t - 1.6537,
+            e: -0.0109 * t + 0.0529,
+        };
+
+        let sun_cos_radius: f64 = SUN_ANGULAR_RADIUS_DEGREES.to_radians().cos();
+        let sun_solid_angle: f64 = 2.0 * PI * (1.0 - sun_cos_radius);
+        let sun_radiance: Color = if sun_direction.y > 0.0 {
+            sun_transmittance(theta_sun, turbidity)
+                * (SUN_ILLUMINANCE * SKY_SCALE / sun_solid_angle)
+        } else {
+            Color::black()
+        };
+
+        Arc::new(PhysicalSky {
+            sun_direction,
+            sun_cos_radius,
+            sun_radiance,
+            theta_sun,
+            zenith: Point3::new(zenith_x, zenith_y, zenith_luminance),
+            perez_luminance,
+            perez_x,
+            perez_y,
+        })
+    }
+
+    pub fn sun_direction(&self) -> Vector3 {
+        self.sun_direction
+    }
+
+    /// Radiance of the sky alone, without the sun, along the unit vector direction
+    fn sky_value(&self, direction: &Vector3) -> Color {
+        if direction.y <= 0.0 {
+            return Color::black();
+        }
+
+        let theta: f64 = direction.y.min(1.0).acos();
+        let gamma: f64 = dot(direction, &self.sun_direction).clamp(-1.0, 1.0).acos();
+
+        let relative = |perez: &Perez, zenith_value: f64| -> f64 {
+            zenith_value * perez.evaluate(theta, gamma) / perez.evaluate(0.0, self.theta_sun)
+        };
+
+        let x: f64 = relative(&self.perez_x, self.zenith.x);
+        let y: f64 = relative(&self.perez_y, self.zenith.y);
+        let luminance: f64 = relative(&self.perez_luminance, self.zenith.z) * SKY_SCALE;
+
+        // From xyY to XYZ
+        let xyz: Point3 = Point3::new(x * luminance / y, luminance, (1.0 - x - y) * luminance / y);
+        let rgb: Color = xyz_to_rgb(xyz);
+
+        Color::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+    }
+
+    fn sun_is_visible(&self) -> bool {
+        self.sun_direction.y > 0.0
+    }
+}
+
+impl Environment for PhysicalSky {
+    fn value(&self, direction: &Vector3) -> Color {
+        let direction: Vector3 = unit_vector(*direction);
+
+        if self.sun_is_visible() && dot(&direction, &self.sun_direction) >= self.sun_cos_radius {
+            return self.sky_value(&direction) + self.sun_radiance;
+        }
+        self.sky_value(&direction)
+    }
+
+    fn sample(&self, light_sample: &mut LightSample) -> bool {
+        // Either a uniform direction inside the cone of the sun or a cosine weighted direction around the zenith
+        light_sample.direction =
+            if self.sun_is_visible() && rand::random_range(0.0..1.0) < SUN_SAMPLING_PROBABILITY {
+                sample_cone(&self.sun_direction, self.sun_cos_radius)
+            } else {
+                // Like Lambertian: the sum is near zero when the random vector points straight down
+                let temp: Vector3 = Point3::new(0.0, 1.0, 0.0) + random_unit_vector();
+                if temp.is_near_zero() {
+                    Point3::new(0.0, 1.0, 0.0)
+                } else {
+                    unit_vector(temp)
+                }
+            };
+
+        light_sample.pdf = self.pdf(&light_sample.direction);
+        // A NaN pdf fails every comparison, so it has to be checked on its own
+        if light_sample.pdf.is_nan() || light_sample.pdf <= 0.0 {
+            return false;
+        }
+        light_sample.distance = f64::INFINITY;
+        light_sample.radiance = self.value(&light_sample.direction);
+
+        true
+    }
+
+    fn pdf(&self, direction: &Vector3) -> f64 {
+        let direction: Vector3 = unit_vector(*direction);
+        let sky_pdf: f64 = direction.y.max(0.0) / PI;
+
+        if !self.sun_is_visible() {
+            return sky_pdf;
+        }
+
+        let sun_pdf: f64 = if dot(&direction, &self.sun_direction) >= self.sun_cos_radius {
+            1.0 / (2.0 * PI * (1.0 - self.sun_cos_radius))
+        } else {
+            0.0
+        };
+
+        SUN_SAMPLING_PROBABILITY * sun_pdf + (1.0 - SUN_SAMPLING_PROBABILITY) * sky_pdf
+    }
+}
+
+/// Fraction of the sunlight that reaches the ground for the red, green and blue wavelengths, taking into account
+/// Rayleigh scattering by the air and Mie scattering by aerosols (Ångström's formula)
+fn sun_transmittance(theta_sun: f64, turbidity: f64) -> Color {
+    // Relative optical air mass, Kasten and Young (1989)
+    let air_mass: f64 =
+        1.0 / (theta_sun.cos() + 0.15 * (93.885 - theta_sun.to_degrees()).powf(-1.253));
+    let beta: f64 = 0.04608 * turbidity - 0.04586;
+
+    let transmittance = |wavelength_micrometers: f64| -> f64 {
+        let rayleigh: f64 = 0.008735 * wavelength_micrometers.powf(-4.08);
+        let aerosol: f64 = beta * wavelength_micrometers.powf(-1.3);
+        (-air_mass * (rayleigh + aerosol)).exp()
+    };
+
+    Color::new(
+        transmittance(0.680),
+        transmittance(0.550),
+        transmittance(0.440),
+    )
+}
+
+/// Returns a uniformly distributed unit vector inside the cone around axis whose half angle has cosine cos_max
+fn sample_cone(axis: &Vector3, cos_max: f64) -> Vector3 {
+    let cos_theta: f64 = 1.0 - rand::random_range(0.0..1.0) * (1.0 - cos_max);
+    let sin_theta: f64 = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi: f64 = rand::random_range(0.0..2.0 * PI);
+
+    let (tangent, bitangent) = orthonormal_basis(axis);
+
+    sin_theta * phi.cos() * tangent + sin_theta * phi.sin() * bitangent + cos_theta * *axis
+}
@@ -2,7 +2,9 @@ pub mod aabb;
 pub mod bvh;
 pub mod camera;
 pub mod hittable;
+pub mod light;
 pub mod material;
+pub mod noise;
 pub mod perlin;
 pub mod point3;
 pub mod ray;
@@ -12,6 +14,7 @@ pub mod texture;
 use std::sync::Arc;
 use std::time::Instant;
 
+use image::{ImageBuffer, Rgb};
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
 
@@ -23,17 +26,44 @@ use crate::hittable::quadric::{Quadric, quadric_sphere};
 use crate::hittable::triangle::Triangle;
 use crate::hittable::{
     constant_medium::ConstantMedium,
+    heterogeneous_medium::{HeterogeneousMedium, TurbulenceDensity},
     hittable_list::HittableList,
     parallelogram::{Parallelogram, create_box},
     quadric::y_cylinder,
     sphere::Sphere,
+    voxel_grid::{GridField, GridPlacement, VoxelGrid, grid_medium},
     {RotateY, Translate},
 };
-use crate::material::{Dielectric, DiffuseLight, Lambertian, Metal, dielectric, metal};
-use crate::perlin::create_perlin_noise;
+use crate::light::Light;
+use crate::light::delta::{DirectionalLight, PointLight, SpotLight};
+use crate::light::environment::{Environment, ImageEnvironment};
+use crate::light::sky::PhysicalSky;
+use crate::material::coated::Coated;
+use crate::material::conductor::Conductor;
+use crate::material::cutout::Cutout;
+use crate::material::dispersion::Dispersion;
+use crate::material::mix::MixMaterial;
+use crate::material::normal_map::NormalMapped;
+use crate::material::oren_nayar::OrenNayar;
+use crate::material::principled::Principled;
+use crate::material::rough_dielectric::RoughDielectric;
+use crate::material::subsurface::Subsurface;
+use crate::material::{
+    BlackBody, BlackBodyMedium, Dielectric, DiffuseLight, Lambertian, Material, Metal, dielectric,
+    metal,
+};
+use crate::noise::WorleyDistance;
+use crate::perlin::{Fbm, PerlinNoise, create_perlin_noise};
 use crate::point3::color::Color;
-use crate::point3::{Point3, random_vector};
-use crate::texture::{CheckerTexture, ImageTexture, PerlinNoiseTexture, Texture};
+use crate::point3::{Point3, Vector3, random_vector};
+use crate::ray::next_medium_id;
+use crate::texture::procedural::{
+    ColorRamp, ColorRampTexture, MarbleTexture, SimplexTexture, WoodTexture, WorleyTexture,
+};
+use crate::texture::{
+    CheckerTexture, ColorSpace, FbmTexture, ImageTexture, NoiseStyle, PerlinNoiseTexture,
+    SolidColor, Texture, TextureFilter, TransformedTexture, WrapMode,
+};
 
 // To do: once new() is implemented for hittables, materials and textures standarize the creation of objects in main
 // To do: better way to handle creating scenes
@@ -77,6 +107,10 @@ fn many_spheres() {
                     // Glass
                     let sphere_material: Dielectric = Dielectric {
                         refraction_index: 1.5,
+                        absorption: Color::black(),
+                        dispersion: None,
+                        priority: 0,
+                        medium_id: next_medium_id(),
                     };
                     world.add(Sphere::new(center, 0.2, Arc::new(sphere_material)));
                 }
@@ -86,6 +120,10 @@ fn many_spheres() {
 
     let material1: Dielectric = Dielectric {
         refraction_index: 1.5,
+        absorption: Color::black(),
+        dispersion: None,
+        priority: 0,
+        medium_id: next_medium_id(),
     };
     world.add(Sphere::new(
         Point3::new(0.0, 1.0, 0.0),
@@ -141,8 +179,9 @@ fn many_spheres() {
         vfov,
         thin_lens,
         camera_position,
-        Color::blue(),
-    );
+        Color::black(),
+    )
+    .with_environment(PhysicalSky::new(35.0, 60.0, 3.0));
 
     // To do: Make this a parameter that can be passed in the console
     // If you want to compare without the bvh
@@ -475,12 +514,19 @@ fn simple_light() {
 
 /// Creates an empty cornell box, returning the HittableList with the quadrilaterals and light
 fn create_empty_cornell_box() -> HittableList {
+    create_cornell_box_with_walls(&|color| Lambertian::from_color(color))
+}
+
+/// The Cornell box, with the material of the walls given the color of each one
+fn create_cornell_box_with_walls(wall: &dyn Fn(Color) -> Arc<dyn Material>) -> HittableList {
     let mut world: HittableList = HittableList::default();
 
-    let red: Arc<Lambertian> = Lambertian::from_color(Point3::new(0.65, 0.05, 0.05));
-    let white: Arc<Lambertian> = Lambertian::from_color(Point3::new(0.73, 0.73, 0.73));
-    let green: Arc<Lambertian> = Lambertian::from_color(Point3::new(0.12, 0.45, 0.15));
-    let diffuse_light: Arc<DiffuseLight> = DiffuseLight::from_color(Point3::new(15.0, 15.0, 15.0));
+    let red: Arc<dyn Material> = wall(Point3::new(0.65, 0.05, 0.05));
+    let white: Arc<dyn Material> = wall(Point3::new(0.73, 0.73, 0.73));
+    let green: Arc<dyn Material> = wall(Point3::new(0.12, 0.45, 0.15));
+    // The light faces down, like a softbox on the ceiling
+    let diffuse_light: Arc<DiffuseLight> =
+        DiffuseLight::new(SolidColor::new(Color::white()), 15.0, false);
 
     world.add(Parallelogram::new(
         Point3::new(555.0, 0.0, 0.0),
@@ -1370,6 +1416,1634 @@ fn bust() {
     cam.thrender(&world.to_hittable_slice());
 }
 
+fn environment_spheres() {
+    let mut world: HittableList = HittableList::default();
+
+    let ground: Arc<Lambertian> = Lambertian::from_color(Color::new(0.5, 0.5, 0.5));
+    world.add(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground));
+
+    world.add(Sphere::new(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Lambertian::from_color(Color::new(0.73, 0.73, 0.73)),
+    ));
+    world.add(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        dielectric(1.5),
+    ));
+    world.add(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        metal(Color::new(0.8, 0.8, 0.8), 0.0),
+    ));
+
+    // Any equirectangular image works, but .hdr and .exr files give much better lighting
+    let environment: Arc<dyn Environment> =
+        ImageEnvironment::new_or_fallback("textures/environment.hdr", 1.0);
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::low();
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 2.0, 10.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::black(),
+    )
+    .with_environment(environment);
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn delta_lights() {
+    let mut world: HittableList = HittableList::default();
+
+    let white: Arc<Lambertian> = Lambertian::from_color(Color::new(0.73, 0.73, 0.73));
+    world.add(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        white.clone(),
+    ));
+    world.add(Sphere::new(Point3::new(-2.2, 1.0, 0.0), 1.0, white.clone()));
+    world.add(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Lambertian::from_color(Color::new(0.65, 0.05, 0.05)),
+    ));
+    world.add(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        metal(Color::new(0.8, 0.8, 0.8), 0.3),
+    ));
+
+    // A warm key light, a cool rim light and a dim fill coming from far away
+    let lights: Vec<Arc<dyn Light>> = vec![
+        PointLight::new(
+            Point3::new(-4.0, 5.0, 4.0),
+            Color::new(1.0, 0.85, 0.7),
+            800.0,
+        ),
+        SpotLight::new(
+            Point3::new(3.0, 6.0, -4.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Color::new(0.7, 0.8, 1.0),
+            600.0,
+            15.0,
+            25.0,
+        ),
+        DirectionalLight::new(Point3::new(1.0, -1.0, -1.0), Color::white(), 0.5),
+    ];
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::low();
+
+    let background_color: Color = Color::black();
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 2.0, 10.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        background_color,
+    )
+    .with_lights(lights);
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn conductors() {
+    let mut world: HittableList = HittableList::default();
+
+    let checker: Arc<CheckerTexture> =
+        CheckerTexture::from_colors(0.5, Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9));
+    world.add(Sphere::new(
+        Point3::new(0.0, -100_000.0, 0.0),
+        100_000.0,
+        Lambertian::from_texture(checker),
+    ));
+
+    // From left to right gold, copper, aluminium and silver. Roughness grows from the front row to the back row
+    let presets: [fn(f64) -> Arc<Conductor>; 4] = [
+        Conductor::gold,
+        Conductor::copper,
+        Conductor::aluminium,
+        Conductor::silver,
+    ];
+    let roughnesses: [f64; 3] = [0.0, 0.2, 0.5];
+
+    for (i, preset) in presets.iter().enumerate() {
+        for (j, roughness) in roughnesses.iter().enumerate() {
+            world.add(Sphere::new(
+                Point3::new(-3.6 + 2.4 * i as f64, 1.0, 2.4 - 3.0 * j as f64),
+                1.0,
+                preset(*roughness),
+            ));
+        }
+    }
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::low();
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 3.0, 16.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::black(),
+    )
+    .with_environment(PhysicalSky::new(35.0, 60.0, 3.0));
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn frosted_glass() {
+    let mut world: HittableList = HittableList::default();
+
+    let checker: Arc<CheckerTexture> =
+        CheckerTexture::from_colors(0.5, Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9));
+    world.add(Sphere::new(
+        Point3::new(0.0, -100_000.0, 0.0),
+        100_000.0,
+        Lambertian::from_texture(checker),
+    ));
+
+    // Glass spheres going from polished to ground glass
+    let roughnesses: [f64; 4] = [0.0, 0.1, 0.3, 0.6];
+    for (i, roughness) in roughnesses.iter().enumerate() {
+        world.add(Sphere::new(
+            Point3::new(-3.6 + 2.4 * i as f64, 1.0, 1.5),
+            1.0,
+            RoughDielectric::new(1.5, *roughness),
+        ));
+    }
+
+    // A frosted pane blurring colored spheres behind it
+    world.add(HittableSlice::from_hittable_list(create_box(
+        Point3::new(-4.0, 0.0, -1.5),
+        Point3::new(4.0, 2.5, -1.4),
+        RoughDielectric::new(1.5, 0.4),
+    )));
+    let colors: [Color; 3] = [
+        Color::new(0.8, 0.1, 0.1),
+        Color::new(0.1, 0.8, 0.1),
+        Color::new(0.1, 0.1, 0.8),
+    ];
+    for (i, color) in colors.iter().enumerate() {
+        world.add(Sphere::new(
+            Point3::new(-2.4 + 2.4 * i as f64, 0.8, -4.0),
+            0.8,
+            Lambertian::from_color(*color),
+        ));
+    }
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    // Rays bounce inside the glass many times before getting out
+    let image_quality: ImageQuality = ImageQuality::new(50, 20);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 3.0, 16.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::black(),
+    )
+    .with_environment(PhysicalSky::new(35.0, 60.0, 3.0));
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn colored_glass() {
+    let mut world: HittableList = HittableList::default();
+
+    let checker: Arc<CheckerTexture> =
+        CheckerTexture::from_colors(0.5, Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9));
+    world.add(Sphere::new(
+        Point3::new(0.0, -100_000.0, 0.0),
+        100_000.0,
+        Lambertian::from_texture(checker),
+    ));
+
+    // The same glass in spheres of growing size: the tint deepens with the thickness
+    let radii: [f64; 3] = [0.4, 0.8, 1.2];
+    for (i, radius) in radii.iter().enumerate() {
+        world.add(Sphere::new(
+            Point3::new(-3.6 + 2.6 * i as f64, *radius, 0.0),
+            *radius,
+            Dielectric::tinted(1.5, Color::new(0.2, 0.6, 0.9), 1.0),
+        ));
+    }
+    world.add(Sphere::new(
+        Point3::new(4.0, 1.0, 0.0),
+        1.0,
+        RoughDielectric::tinted(1.5, 0.2, Color::new(0.9, 0.5, 0.1), 1.0),
+    ));
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    // Rays bounce inside the glass many times before getting out
+    let image_quality: ImageQuality = ImageQuality::new(50, 20);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 3.0, 16.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::black(),
+    )
+    .with_environment(PhysicalSky::new(35.0, 60.0, 3.0));
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn dispersion() {
+    let mut world: HittableList = HittableList::default();
+
+    // Sharp black and white edges show the colored fringes best
+    let checker: Arc<CheckerTexture> =
+        CheckerTexture::from_colors(0.3, Color::new(0.05, 0.05, 0.05), Color::white());
+    world.add(Sphere::new(
+        Point3::new(0.0, -100_000.0, 0.0),
+        100_000.0,
+        Lambertian::from_texture(checker),
+    ));
+
+    // From left to right the dispersion grows: common glass, dense flint glass and diamond
+    world.add(Sphere::new(
+        Point3::new(-2.4, 1.0, 0.0),
+        1.0,
+        Dielectric::dispersive(Dispersion::bk7()),
+    ));
+    world.add(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Dielectric::dispersive(Dispersion::dense_flint()),
+    ));
+    world.add(Sphere::new(
+        Point3::new(2.4, 1.0, 0.0),
+        1.0,
+        Dielectric::dispersive(Dispersion::diamond()),
+    ));
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    // Each path only carries one wavelength inside the glass, so it needs more samples to average out the colors
+    let image_quality: ImageQuality = ImageQuality::new(100, 20);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 3.0, 12.0);
+    let look_at: Point3 = Point3::new(0.0, 0.8, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::blue(),
+    );
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn spectral() {
+    let mut world: HittableList = HittableList::default();
+
+    let checker: Arc<CheckerTexture> =
+        CheckerTexture::from_colors(0.3, Color::new(0.05, 0.05, 0.05), Color::white());
+    world.add(Sphere::new(
+        Point3::new(0.0, -100_000.0, 0.0),
+        100_000.0,
+        Lambertian::from_texture(checker),
+    ));
+
+    // Things that need wavelengths: a diamond, metals and black bodies of different temperatures
+    world.add(Sphere::new(
+        Point3::new(-2.4, 1.0, 0.0),
+        1.0,
+        Dielectric::dispersive(Dispersion::diamond()),
+    ));
+    world.add(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Conductor::gold(0.2),
+    ));
+    world.add(Sphere::new(
+        Point3::new(2.4, 1.0, 0.0),
+        1.0,
+        Conductor::copper(0.2),
+    ));
+
+    let temperatures: [f64; 4] = [1800.0, 2700.0, 4000.0, 6500.0];
+    for (i, temperature) in temperatures.iter().enumerate() {
+        world.add(Sphere::new(
+            Point3::new(-2.4 + 1.6 * i as f64, 0.3, 2.0),
+            0.3,
+            BlackBody::new(*temperature, 1.5, true),
+        ));
+    }
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    // Every sample only carries one wavelength, so it needs more of them to average out the colors
+    let image_quality: ImageQuality = ImageQuality::new(100, 20);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 3.0, 12.0);
+    let look_at: Point3 = Point3::new(0.0, 0.8, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::black(),
+    )
+    .with_environment(PhysicalSky::new(35.0, 60.0, 3.0))
+    .with_spectral_rendering();
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn principled() {
+    let mut world: HittableList = HittableList::default();
+
+    let checker: Arc<CheckerTexture> =
+        CheckerTexture::from_colors(0.5, Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9));
+    world.add(Sphere::new(
+        Point3::new(0.0, -100_000.0, 0.0),
+        100_000.0,
+        Lambertian::from_texture(checker),
+    ));
+
+    // Front row: plastic, rubber, brushed gold and car paint
+    let front: [Principled; 4] = [
+        Principled::from_color(Color::new(0.1, 0.3, 0.8)).with_roughness(0.2),
+        Principled::from_color(Color::new(0.1, 0.1, 0.1)).with_roughness(0.9),
+        Principled::from_color(Color::new(1.0, 0.78, 0.34))
+            .with_metallic(1.0)
+            .with_roughness(0.35),
+        Principled::from_color(Color::new(0.6, 0.05, 0.05))
+            .with_metallic(0.5)
+            .with_roughness(0.5)
+            .with_clearcoat(1.0, 1.0),
+    ];
+    // Back row: velvet, frosted green glass and a metal that is only polished in some places
+    let back: [Principled; 3] = [
+        Principled::from_color(Color::new(0.4, 0.05, 0.3))
+            .with_roughness(1.0)
+            .with_sheen(1.0, 0.8),
+        Principled::from_color(Color::new(0.8, 1.0, 0.85))
+            .with_roughness(0.15)
+            .with_transmission(1.0, 1.5),
+        Principled {
+            metallic: CheckerTexture::from_colors(0.2, Color::white(), Color::black()),
+            roughness: CheckerTexture::from_colors(0.2, Color::new(0.1, 0.1, 0.1), Color::white()),
+            ..Principled::from_color(Color::new(0.9, 0.9, 0.9))
+        },
+    ];
+
+    for (i, material) in front.into_iter().enumerate() {
+        world.add(Sphere::new(
+            Point3::new(-3.6 + 2.4 * i as f64, 1.0, 2.4),
+            1.0,
+            Arc::new(material),
+        ));
+    }
+    for (i, material) in back.into_iter().enumerate() {
+        world.add(Sphere::new(
+            Point3::new(-2.4 + 2.4 * i as f64, 1.0, -0.6),
+            1.0,
+            Arc::new(material),
+        ));
+    }
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::new(100, 20);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 3.0, 16.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::black(),
+    )
+    .with_environment(PhysicalSky::new(35.0, 60.0, 3.0));
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn layered_materials() {
+    let mut world: HittableList = HittableList::default();
+
+    let checker: Arc<CheckerTexture> =
+        CheckerTexture::from_colors(0.5, Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9));
+    world.add(Sphere::new(
+        Point3::new(0.0, -100_000.0, 0.0),
+        100_000.0,
+        Lambertian::from_texture(checker),
+    ));
+
+    // Rusty aluminium: the noise decides where the rust is
+    let rust_mask: Arc<PerlinNoiseTexture> = Arc::new(PerlinNoiseTexture {
+        perlin_noise: create_perlin_noise(),
+        scale: 4.0,
+    });
+    let rusty: Arc<MixMaterial> = MixMaterial::with_mask(
+        Conductor::aluminium(0.2),
+        Lambertian::from_color(Color::new(0.45, 0.15, 0.05)),
+        rust_mask,
+    );
+    // Half diffuse, half mirror everywhere
+    let half_mirror: Arc<MixMaterial> = MixMaterial::new(
+        Lambertian::from_color(Color::new(0.1, 0.4, 0.2)),
+        Conductor::silver(0.0),
+        0.5,
+    );
+    // Glossy paint, varnished checkers and lacquered brushed copper
+    let paint: Arc<Coated> = Coated::new(
+        Lambertian::from_color(Color::new(0.7, 0.05, 0.05)),
+        1.5,
+        0.0,
+    );
+    let varnished: Arc<Coated> = Coated::tinted(
+        Lambertian::from_texture(CheckerTexture::from_colors(
+            0.1,
+            Color::new(0.6, 0.6, 0.6),
+            Color::new(0.1, 0.1, 0.3),
+        )),
+        1.5,
+        0.1,
+        Color::new(0.9, 0.7, 0.3),
+    );
+    let lacquered: Arc<Coated> = Coated::new(Conductor::copper(0.5), 1.5, 0.0);
+
+    let materials: [Arc<dyn Material>; 5] = [rusty, half_mirror, paint, varnished, lacquered];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add(Sphere::new(
+            Point3::new(-4.8 + 2.4 * i as f64, 1.0, 0.0),
+            1.0,
+            material,
+        ));
+    }
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::new(100, 20);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 3.0, 16.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::black(),
+    )
+    .with_environment(PhysicalSky::new(35.0, 60.0, 3.0));
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn cornell_plaster() {
+    // Plaster walls, a clay sphere on the left and a Lambertian one of the same color on the right
+    let mut world: HittableList =
+        create_cornell_box_with_walls(&|color| OrenNayar::from_color(color, 30.0));
+    let terracotta: Color = Color::new(0.7, 0.35, 0.2);
+
+    world.add(Sphere::new(
+        Point3::new(400.0, 120.0, 300.0),
+        120.0,
+        OrenNayar::from_color(terracotta, 40.0),
+    ));
+    world.add(Sphere::new(
+        Point3::new(155.0, 120.0, 300.0),
+        120.0,
+        Lambertian::from_color(terracotta),
+    ));
+
+    let aspect_ratio: f64 = 1.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::new(100, 50);
+
+    let background_color: Point3 = Point3::new(0.0, 0.0, 0.0);
+
+    let vfov: f64 = 40.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(278.0, 278.0, -800.0);
+    let look_at: Point3 = Point3::new(278.0, 278.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        background_color,
+    );
+
+    cam.thrender(&HittableSlice::from_hittable_list(world));
+}
+
+fn bump_maps() {
+    let mut world: HittableList = HittableList::default();
+
+    // A tiled floor: the checker is only used as a height map, so only the grooves between the tiles show
+    let tiles: Arc<CheckerTexture> =
+        CheckerTexture::from_colors(0.5, Color::white(), Color::black());
+    world.add(Parallelogram::new(
+        Point3::new(-1000.0, 0.0, 1000.0),
+        Point3::new(2000.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -2000.0),
+        NormalMapped::bump_map(
+            Lambertian::from_color(Color::new(0.7, 0.7, 0.6)),
+            tiles,
+            0.02,
+        ),
+    ));
+
+    // The same noise bumps a diffuse sphere and a copper one. The sphere on the right is smooth, for comparison
+    let noise: Arc<PerlinNoiseTexture> = Arc::new(PerlinNoiseTexture {
+        perlin_noise: create_perlin_noise(),
+        scale: 8.0,
+    });
+    world.add(Sphere::new(
+        Point3::new(-2.4, 1.0, 0.0),
+        1.0,
+        NormalMapped::bump_map(
+            Lambertian::from_color(Color::new(0.8, 0.5, 0.3)),
+            noise.clone(),
+            0.05,
+        ),
+    ));
+    world.add(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        NormalMapped::bump_map(Conductor::copper(0.1), noise, 0.05),
+    ));
+    world.add(Sphere::new(
+        Point3::new(2.4, 1.0, 0.0),
+        1.0,
+        Conductor::copper(0.1),
+    ));
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::new(100, 20);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 3.0, 12.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::black(),
+    )
+    .with_environment(PhysicalSky::new(35.0, 60.0, 3.0));
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn cutouts() {
+    let mut world: HittableList = HittableList::default();
+
+    world.add(Parallelogram::new(
+        Point3::new(-1000.0, 0.0, 1000.0),
+        Point3::new(2000.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -2000.0),
+        Lambertian::from_color(Color::new(0.5, 0.6, 0.4)),
+    ));
+
+    // A lattice made from a single quad: the black squares of the checker are holes
+    let lattice: Arc<CheckerTexture> =
+        CheckerTexture::from_colors(0.2, Color::white(), Color::black());
+    world.add(Parallelogram::new(
+        Point3::new(-3.0, 0.0, 1.5),
+        Point3::new(6.0, 0.0, 0.0),
+        Point3::new(0.0, 1.5, 0.0),
+        Cutout::new(Lambertian::from_color(Color::new(0.6, 0.4, 0.2)), lattice),
+    ));
+
+    // Behind it, an opaque sphere and one that lets half of the rays through
+    world.add(Sphere::new(
+        Point3::new(-1.2, 1.0, 0.0),
+        1.0,
+        Lambertian::from_color(Color::new(0.2, 0.3, 0.8)),
+    ));
+    world.add(Sphere::new(
+        Point3::new(1.2, 1.0, 0.0),
+        1.0,
+        Cutout::new(
+            Lambertian::from_color(Color::new(0.8, 0.2, 0.2)),
+            SolidColor::new(Color::new(0.5, 0.5, 0.5)),
+        ),
+    ));
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::new(100, 20);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 2.0, 12.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::black(),
+    )
+    .with_environment(PhysicalSky::new(35.0, 60.0, 3.0));
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn subsurface() {
+    let mut world: HittableList = HittableList::default();
+
+    world.add(Parallelogram::new(
+        Point3::new(-1000.0, 0.0, 1000.0),
+        Point3::new(2000.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -2000.0),
+        Lambertian::from_color(Color::new(0.4, 0.4, 0.4)),
+    ));
+
+    // Wax, skin and marble. Mean free paths are relative to the radius of the spheres
+    world.add(Sphere::new(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Subsurface::from_color(Color::new(0.9, 0.75, 0.5), Color::new(0.5, 0.4, 0.3), 1.45),
+    ));
+    world.add(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Subsurface::from_color(Color::new(0.85, 0.6, 0.5), Color::new(0.4, 0.15, 0.1), 1.4),
+    ));
+    world.add(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        Subsurface::from_color(Color::new(0.9, 0.9, 0.88), Color::new(0.1, 0.1, 0.1), 1.5),
+    ));
+    // It works the same on meshes, as long as they are closed:
+    // world.add(load_model("models/Emperor.obj", 0.02, Subsurface::from_color(...)));
+
+    // Lit from behind, so that the light coming through the spheres shows
+    let lights: Vec<Arc<dyn Light>> = vec![PointLight::new(
+        Point3::new(-2.0, 3.0, -3.0),
+        Color::new(1.0, 0.9, 0.8),
+        1000.0,
+    )];
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    // The walks inside need many bounces
+    let image_quality: ImageQuality = ImageQuality::new(100, 200);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 2.0, 12.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::new(0.05, 0.06, 0.08),
+    )
+    .with_lights(lights);
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn heterogeneous_media() {
+    let mut world: HittableList = HittableList::default();
+
+    world.add(Parallelogram::new(
+        Point3::new(-1000.0, 0.0, 1000.0),
+        Point3::new(2000.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -2000.0),
+        Lambertian::from_color(Color::new(0.4, 0.5, 0.3)),
+    ));
+
+    // A puffy cloud
+    let cloud_boundary: Arc<Sphere> = Arc::new(Sphere::new(
+        Point3::new(-1.5, 2.0, 0.0),
+        1.5,
+        Lambertian::from_color(Color::white()),
+    ));
+    world.add(HeterogeneousMedium::from_color(
+        cloud_boundary,
+        Arc::new(TurbulenceDensity {
+            perlin_noise: create_perlin_noise(),
+            frequency: 1.5,
+            octaves: 5,
+            max_density: 8.0,
+        }),
+        Color::new(0.95, 0.95, 0.95),
+    ));
+
+    // Patchy fog over the ground, around a sphere
+    world.add(Sphere::new(
+        Point3::new(2.0, 0.8, 0.0),
+        0.8,
+        Lambertian::from_color(Color::new(0.7, 0.2, 0.1)),
+    ));
+    let fog_boundary: Arc<HittableSlice> = Arc::new(HittableSlice::from_hittable_list(create_box(
+        Point3::new(0.0, 0.0, -2.0),
+        Point3::new(4.0, 1.0, 2.0),
+        Lambertian::from_color(Color::white()),
+    )));
+    world.add(HeterogeneousMedium::from_color(
+        fog_boundary,
+        Arc::new(TurbulenceDensity {
+            perlin_noise: create_perlin_noise(),
+            frequency: 2.0,
+            octaves: 3,
+            max_density: 3.0,
+        }),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::new(100, 50);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 2.0, 12.0);
+    let look_at: Point3 = Point3::new(0.0, 1.5, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::black(),
+    )
+    .with_environment(PhysicalSky::new(35.0, 60.0, 3.0));
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn foggy_lights() {
+    let mut world: HittableList = HittableList::default();
+
+    let white: Arc<Lambertian> = Lambertian::from_color(Color::new(0.73, 0.73, 0.73));
+    world.add(Parallelogram::new(
+        Point3::new(-1000.0, 0.0, 1000.0),
+        Point3::new(2000.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -2000.0),
+        white.clone(),
+    ));
+    world.add(Sphere::new(Point3::new(-1.5, 1.0, 0.0), 1.0, white.clone()));
+    world.add(Sphere::new(
+        Point3::new(1.5, 1.0, 0.0),
+        1.0,
+        Lambertian::from_color(Color::new(0.2, 0.3, 0.7)),
+    ));
+
+    // Fog that scatters mostly forward, so the lights look brighter when looking towards them
+    let fog_boundary: Arc<HittableSlice> = Arc::new(HittableSlice::from_hittable_list(create_box(
+        Point3::new(-8.0, 0.0, -8.0),
+        Point3::new(8.0, 6.0, 8.0),
+        white,
+    )));
+    world.add(ConstantMedium::anisotropic(
+        fog_boundary,
+        0.05,
+        Color::new(0.9, 0.9, 0.9),
+        0.7,
+    ));
+
+    // A spotlight behind the spheres, pointing at the camera, and one from above
+    let lights: Vec<Arc<dyn Light>> = vec![
+        SpotLight::new(
+            Point3::new(0.0, 3.0, -5.0),
+            Point3::new(0.0, 1.0, 5.0),
+            Color::new(1.0, 0.85, 0.7),
+            40.0,
+            20.0,
+            30.0,
+        ),
+        SpotLight::new(
+            Point3::new(1.5, 5.5, 0.0),
+            Point3::new(1.5, 0.0, 0.0),
+            Color::new(0.7, 0.8, 1.0),
+            60.0,
+            10.0,
+            15.0,
+        ),
+    ];
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::new(50, 20);
+
+    let vfov: f64 = 40.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 2.0, 7.0);
+    let look_at: Point3 = Point3::new(0.0, 1.5, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::black(),
+    )
+    .with_lights(lights);
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn voxel_fire() {
+    let mut world: HittableList = HittableList::default();
+
+    world.add(Parallelogram::new(
+        Point3::new(-1000.0, 0.0, 1000.0),
+        Point3::new(2000.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -2000.0),
+        Lambertian::from_color(Color::new(0.3, 0.3, 0.3)),
+    ));
+
+    // A plume made here instead of loaded from a simulation, which would be:
+    // let density: Arc<VoxelGrid> = Arc::new(VoxelGrid::load_raw("volumes/plume_density.raw"));
+    let dimensions: [usize; 3] = [32, 64, 32];
+    let noise: PerlinNoise = create_perlin_noise();
+    let mut density: Vec<f64> = Vec::new();
+    let mut temperature: Vec<f64> = Vec::new();
+    for k in 0..dimensions[2] {
+        for j in 0..dimensions[1] {
+            for i in 0..dimensions[0] {
+                let local: Point3 = Point3::new(
+                    (i as f64 + 0.5) / dimensions[0] as f64,
+                    (j as f64 + 0.5) / dimensions[1] as f64,
+                    (k as f64 + 0.5) / dimensions[2] as f64,
+                );
+                // A column that gets wider as it goes up, broken up by noise
+                let width: f64 = 0.15 + 0.25 * local.y;
+                let distance: f64 = ((local.x - 0.5).powi(2) + (local.z - 0.5).powi(2)).sqrt();
+                let falloff: f64 = (1.0 - distance / width).max(0.0);
+                let turbulence: f64 = noise.turbulence(&(6.0 * local), 4);
+
+                density.push(falloff * (0.3 + turbulence));
+                // Hot at the bottom and in the middle
+                temperature
+                    .push(5000.0 * falloff.sqrt() * (1.0 - local.y) * (0.6 + 0.4 * turbulence));
+            }
+        }
+    }
+
+    let placement: GridPlacement = GridPlacement::new(
+        Point3::new(-1.0, 0.0, -1.0),
+        Vector3::new(2.0, 4.0, 2.0),
+        20.0,
+    );
+    let temperature: GridField = GridField::new(
+        Arc::new(VoxelGrid::new(dimensions, temperature)),
+        placement,
+        1.0,
+    );
+    world.add(
+        grid_medium(
+            Arc::new(VoxelGrid::new(dimensions, density)),
+            6.0,
+            placement,
+            Color::new(0.5, 0.5, 0.5),
+        )
+        .with_phase_function(BlackBodyMedium::new(
+            Color::new(0.5, 0.5, 0.5),
+            Arc::new(temperature),
+            400.0,
+        )),
+    );
+
+    let aspect_ratio: f64 = 1.0;
+    let image_width: u32 = 400;
+    let image_quality: ImageQuality = ImageQuality::new(100, 20);
+
+    let vfov: f64 = 40.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 2.5, 8.0);
+    let look_at: Point3 = Point3::new(0.0, 2.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::new(0.02, 0.02, 0.03),
+    );
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn underwater() {
+    let mut world: HittableList = HittableList::default();
+
+    // The floor of a pool, with a few things on it
+    world.add(Parallelogram::new(
+        Point3::new(-1000.0, 0.0, 1000.0),
+        Point3::new(2000.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -2000.0),
+        Lambertian::from_texture(CheckerTexture::from_colors(
+            1.0,
+            Color::new(0.9, 0.85, 0.7),
+            Color::new(0.3, 0.5, 0.6),
+        )),
+    ));
+    world.add(Sphere::new(
+        Point3::new(-2.0, 1.0, -1.0),
+        1.0,
+        Lambertian::from_color(Color::new(0.8, 0.3, 0.2)),
+    ));
+    world.add(Sphere::new(
+        Point3::new(1.5, 1.0, 0.0),
+        1.0,
+        dielectric(1.5),
+    ));
+
+    // The water ends at y = 4. The camera is inside of it
+    let water: Arc<Dielectric> = Dielectric::prioritized(1.33, Color::new(0.6, 0.85, 0.9), 10.0, 1);
+    world.add(HittableSlice::from_hittable_list(create_box(
+        Point3::new(-50.0, -1.0, -50.0),
+        Point3::new(50.0, 4.0, 50.0),
+        water.clone(),
+    )));
+
+    // An ice cube floating, half of it out of the water. It wins over the water, so the surface of the water
+    // inside of it is ignored
+    let ice: Arc<Dielectric> = Dielectric::prioritized(1.31, Color::new(0.95, 0.98, 1.0), 1.0, 2);
+    world.add(Translate::new(
+        Arc::new(RotateY::new(
+            Arc::new(HittableSlice::from_hittable_list(create_box(
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.5, 1.5, 1.5),
+                ice,
+            ))),
+            30.0,
+        )),
+        Point3::new(-0.5, 3.2, 1.0),
+    ));
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::new(100, 30);
+
+    let vfov: f64 = 50.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 1.5, 8.0);
+    let look_at: Point3 = Point3::new(0.0, 2.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::new(0.7, 0.8, 1.0),
+    )
+    .inside(&water);
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn texture_mapping() {
+    let mut world: HittableList = HittableList::default();
+
+    world.add(Parallelogram::new(
+        Point3::new(-1000.0, 0.0, 1000.0),
+        Point3::new(2000.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -2000.0),
+        Lambertian::from_color(Color::new(0.5, 0.5, 0.5)),
+    ));
+
+    let (light, dark): (Color, Color) = (Color::new(0.9, 0.9, 0.9), Color::new(0.2, 0.3, 0.1));
+
+    // The same checker in the scene and wrapped around the sphere. The coordinates are scaled along u, which goes
+    // around the sphere, so the squares are square
+    world.add(Sphere::new(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Lambertian::from_texture(CheckerTexture::from_colors(0.4, light, dark)),
+    ));
+    world.add(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Lambertian::from_texture(Arc::new(
+            TransformedTexture::new(CheckerTexture::surface_from_colors(8.0, light, dark))
+                .with_scale(2.0, 1.0),
+        )),
+    ));
+
+    // An image rotated and repeated on a square
+    world.add(Parallelogram::new(
+        Point3::new(1.4, 0.2, 0.5),
+        Point3::new(1.8, 0.0, -0.8),
+        Point3::new(0.0, 1.8, 0.0),
+        Lambertian::from_texture(Arc::new(
+            TransformedTexture::new(ImageTexture::new_or_fallback("textures/earthmap.jpg"))
+                .with_rotation(30.0)
+                .with_tiling(2.0, 2.0),
+        )),
+    ));
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::new(50, 20);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 3.0, 9.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::new(0.7, 0.8, 1.0),
+    );
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn texture_filtering() {
+    let mut world: HittableList = HittableList::default();
+
+    // A small image with thin lines, repeated many times over two long floors going into the distance
+    let lines: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_fn(64, 64, |x, y| {
+        if x % 16 < 2 || y % 16 < 2 {
+            Rgb([30, 30, 30])
+        } else {
+            Rgb([230, 220, 200])
+        }
+    });
+    let floor = |filter: TextureFilter, corner: Point3| -> Parallelogram {
+        let texture: Arc<ImageTexture> = Arc::new(ImageTexture::from_image(
+            &lines,
+            filter,
+            WrapMode::Repeat,
+            ColorSpace::Srgb,
+        ));
+        Parallelogram::new(
+            corner,
+            Point3::new(20.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -200.0),
+            Lambertian::from_texture(Arc::new(
+                TransformedTexture::new(texture).with_tiling(5.0, 50.0),
+            )),
+        )
+    };
+    // Aliased on the left, blurred on the right
+    world.add(floor(TextureFilter::Nearest, Point3::new(-20.0, 0.0, 5.0)));
+    world.add(floor(TextureFilter::Trilinear, Point3::new(0.0, 0.0, 5.0)));
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    // Few samples per pixel, so the aliasing is not averaged away
+    let image_quality: ImageQuality = ImageQuality::new(4, 10);
+
+    let vfov: f64 = 40.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 1.5, 5.0);
+    let look_at: Point3 = Point3::new(0.0, 0.5, -10.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::new(0.7, 0.8, 1.0),
+    );
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn noise_presets() {
+    let mut world: HittableList = HittableList::default();
+
+    world.add(Parallelogram::new(
+        Point3::new(-1000.0, 0.0, 1000.0),
+        Point3::new(2000.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -2000.0),
+        Lambertian::from_texture(FbmTexture::cracks(1, 0.5)),
+    ));
+
+    world.add(Sphere::new(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Lambertian::from_texture(FbmTexture::clouds(2, 1.5)),
+    ));
+    world.add(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Lambertian::from_texture(FbmTexture::rock(3, 2.0)),
+    ));
+    // Low lacunarity and high gain give blotches of similar sizes
+    world.add(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        Lambertian::from_texture(FbmTexture::new(
+            4,
+            3.0,
+            Fbm::new(5).with_lacunarity(1.6).with_gain(0.8),
+            NoiseStyle::Smooth,
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.6, 0.55, 0.35),
+        )),
+    ));
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::new(50, 20);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 3.0, 9.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::new(0.7, 0.8, 1.0),
+    );
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
+fn procedural_textures() {
+    let mut world: HittableList = HittableList::default();
+
+    world.add(Parallelogram::new(
+        Point3::new(-1000.0, 0.0, 1000.0),
+        Point3::new(2000.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -2000.0),
+        Lambertian::from_texture(WorleyTexture::cells(
+            1,
+            1.5,
+            Color::new(0.1, 0.1, 0.1),
+            Color::new(0.5, 0.48, 0.45),
+        )),
+    ));
+
+    world.add(Sphere::new(
+        Point3::new(-3.3, 1.0, 0.0),
+        1.0,
+        Lambertian::from_texture(MarbleTexture::white(2, 4.0)),
+    ));
+    // The rings go around the vertical axis through the origin, so the sphere is moved there and back
+    let wood: Arc<dyn Texture> = Arc::new(
+        TransformedTexture::new(WoodTexture::oak(3, 6.0))
+            .with_point_transform(1.0, Vector3::new(1.1, 0.0, 0.0)),
+    );
+    world.add(Sphere::new(
+        Point3::new(-1.1, 1.0, 0.0),
+        1.0,
+        Lambertian::from_texture(wood),
+    ));
+    // Simplex noise as the height of a small planet
+    world.add(Sphere::new(
+        Point3::new(1.1, 1.0, 0.0),
+        1.0,
+        Lambertian::from_texture(SimplexTexture::new(
+            4,
+            1.2,
+            Fbm::new(6),
+            ColorRamp::new(vec![
+                (0.45, Color::new(0.05, 0.15, 0.45)),
+                (0.5, Color::new(0.75, 0.7, 0.45)),
+                (0.53, Color::new(0.2, 0.45, 0.15)),
+                (0.62, Color::new(0.35, 0.3, 0.25)),
+                (0.7, Color::new(0.35, 0.3, 0.25)),
+                (0.75, Color::white()),
+            ]),
+        )),
+    ));
+    // Any texture can go through a ramp: the distance to the closest cell point, as glowing hot spots cooling down
+    let spots: Arc<dyn Texture> = WorleyTexture::new(
+        5,
+        3.0,
+        WorleyDistance::Closest,
+        ColorRamp::two_colors(Color::white(), Color::black()),
+    );
+    world.add(Sphere::new(
+        Point3::new(3.3, 1.0, 0.0),
+        1.0,
+        DiffuseLight::from_texture(ColorRampTexture::new(
+            spots,
+            ColorRamp::new(vec![
+                (0.3, Color::new(0.05, 0.0, 0.0)),
+                (0.6, Color::new(0.8, 0.1, 0.0)),
+                (0.85, Color::new(1.0, 0.7, 0.1)),
+                (1.0, Color::new(1.0, 1.0, 0.8)),
+            ]),
+        )),
+    ));
+
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let image_width: u32 = 600;
+    let image_quality: ImageQuality = ImageQuality::new(50, 20);
+
+    let vfov: f64 = 30.0;
+    let defocus_angle: f64 = 0.0;
+    let focus_distance: f64 = 10.0;
+
+    let lens: ThinLens = ThinLens {
+        defocus_angle,
+        focus_distance,
+    };
+
+    let look_from: Point3 = Point3::new(0.0, 3.0, 12.0);
+    let look_at: Point3 = Point3::new(0.0, 1.0, 0.0);
+    let view_up: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+    let camera_position: CameraPosition = CameraPosition {
+        look_from,
+        look_at,
+        view_up,
+    };
+
+    let cam: Camera = Camera::new(
+        aspect_ratio,
+        image_width,
+        image_quality,
+        vfov,
+        lens,
+        camera_position,
+        Color::new(0.7, 0.8, 1.0),
+    );
+
+    cam.thrender(&world.to_hittable_slice());
+}
+
 fn main() {
     let now: Instant = Instant::now();
     let scene_number: u32 = 15;
@@ -1393,6 +3067,27 @@ fn main() {
         15 => spherical_mirror(),
         16 => debug_model(),
         17 => bust(),
+        18 => environment_spheres(),
+        19 => delta_lights(),
+        20 => conductors(),
+        21 => frosted_glass(),
+        22 => colored_glass(),
+        23 => dispersion(),
+        24 => spectral(),
+        25 => principled(),
+        26 => layered_materials(),
+        27 => cornell_plaster(),
+        28 => bump_maps(),
+        29 => cutouts(),
+        30 => subsurface(),
+        31 => heterogeneous_media(),
+        32 => foggy_lights(),
+        33 => voxel_fire(),
+        34 => underwater(),
+        35 => texture_mapping(),
+        36 => texture_filtering(),
+        37 => noise_presets(),
+        38 => procedural_textures(),
         _ => final_scene(400, 20, 4),
     }
 
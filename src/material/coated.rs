@@ -0,0 +1,227 @@
+use std::sync::Arc;
+
+use crate::hittable::{HitRecord, SurfaceCoordinate};
+use crate::material::microfacet::{Ggx, LocalFrame, fresnel_dielectric};
+use crate::material::{Material, ScatteredRayAndAttenuation, absorption_from_color};
+use crate::point3::color::Color;
+use crate::point3::{Point3, Vector3, dot, unit_vector};
+use crate::ray::{MediumStack, Ray};
+use crate::texture::{SolidColor, Texture};
+
+/// A thin clear layer of dielectric over another material, like varnished wood or glazed ceramic.
+/// Light is reflected by the coat according to the Fresnel equations, and the rest goes through it (optionally
+/// getting tinted) to the base and back out. The bending of the rays inside the thin coat and the light bouncing
+/// between the coat and the base are ignored
+pub struct Coated {
+    pub base: Arc<dyn Material>,
+    /// Refractive index of the coat, usually around 1.5. Inside other media (a varnished object under water) it is
+    /// relative to the index of the medium
+    pub refraction_index: f64,
+    /// Perceptual roughness of the coat, from 0 (polished) to 1. Only the luminance of the texture is used
+    pub roughness: Arc<dyn Texture>,
+    /// Absorption of the coat, relative to its thickness
+    pub absorption: Color,
+}
+
+impl Coated {
+    pub fn new(base: Arc<dyn Material>, refraction_index: f64, roughness: f64) -> Arc<Coated> {
+        Arc::new(Coated {
+            base,
+            refraction_index,
+            roughness: SolidColor::new(Color::new(roughness, roughness, roughness)),
+            absorption: Color::black(),
+        })
+    }
+
+    /// A coat that tints what is below it: light going straight down to the base and back up gets multiplied by color
+    pub fn tinted(
+        base: Arc<dyn Material>,
+        refraction_index: f64,
+        roughness: f64,
+        color: Color,
+    ) -> Arc<Coated> {
+        Arc::new(Coated {
+            base,
+            refraction_index,
+            roughness: SolidColor::new(Color::new(roughness, roughness, roughness)),
+            absorption: absorption_from_color(color, 2.0),
+        })
+    }
+
+    fn distribution(&self, record: &HitRecord) -> Ggx {
+        Ggx::from_roughness(self.roughness.value_at(record).luminance())
+    }
+
+    /// Refractive index of the coat over the one of the medium the ray is in
+    fn eta(&self, ray_in: &Ray) -> f64 {
+        let outside: f64 = ray_in
+            .media
+            .as_deref()
+            .and_then(MediumStack::current)
+            .map_or(1.0, |medium| medium.refraction_index);
+
+        self.refraction_index / outside
+    }
+
+    /// Fraction of the light that goes through the coat along direction (either way) without being reflected
+    fn transmission(&self, record: &HitRecord, direction: &Vector3, eta: f64) -> f64 {
+        let cos_theta: f64 = dot(&record.normal, &unit_vector(*direction)).abs();
+        1.0 - fresnel_dielectric(cos_theta, eta)
+    }
+
+    /// Fraction of the light that survives crossing the coat down along wo and up along wi. The length of the path
+    /// inside depends on the angle of the refracted rays
+    fn coat_transmittance(
+        &self,
+        record: &HitRecord,
+        wo: &Vector3,
+        wi: &Vector3,
+        eta: f64,
+    ) -> Color {
+        let inside_length = |direction: &Vector3| -> f64 {
+            let cos_theta: f64 = dot(&record.normal, &unit_vector(*direction)).abs();
+            let sin_squared: f64 = (1.0 - cos_theta * cos_theta) / (eta * eta);
+            1.0 / (1.0 - sin_squared).sqrt()
+        };
+        // Half of the thickness each way, as the absorption is for the whole round trip
+        let distance: f64 = 0.5 * (inside_length(wo) + inside_length(wi));
+
+        Color::new(
+            (-self.absorption.x * distance).exp(),
+            (-self.absorption.y * distance).exp(),
+            (-self.absorption.z * distance).exp(),
+        )
+    }
+
+    /// Light that reaches the base and comes back out along direction, counting both crossings of the coat
+    fn through_coat(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let wo: Vector3 = -ray_in.direction;
+        let eta: f64 = self.eta(ray_in);
+
+        self.transmission(record, &wo, eta)
+            * self.transmission(record, direction, eta)
+            * self.coat_transmittance(record, &wo, direction, eta)
+    }
+}
+
+impl Material for Coated {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        // From inside the object only the base is seen
+        if !record.front_face {
+            return self.base.scatter(ray_in, record, sca_att);
+        }
+
+        let ggx: Ggx = self.distribution(record);
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+
+        if wo.z <= 0.0 {
+            return false;
+        }
+
+        // Reflect on the coat with the probability given by the Fresnel equations
+        let reflectance: f64 = fresnel_dielectric(wo.z, self.eta(ray_in));
+        if rand::random_range(0.0..1.0) < reflectance {
+            if ggx.is_smooth() {
+                let wi: Vector3 = Vector3::new(-wo.x, -wo.y, wo.z);
+                sca_att.scattered_ray = Ray::new(record.p, frame.to_world(&wi));
+                sca_att.attenuation = Color::white();
+                sca_att.pdf = 0.0;
+                return true;
+            }
+
+            let wi: Vector3 = match ggx.sample_reflection(&wo) {
+                Some(wi) => wi,
+                None => return false,
+            };
+            sca_att.scattered_ray = Ray::new(record.p, frame.to_world(&wi));
+        } else {
+            if !self.base.scatter(ray_in, record, sca_att) {
+                return false;
+            }
+
+            // A specular base: its probability cancels out with the fraction of light that is not reflected
+            if sca_att.pdf <= 0.0 {
+                let direction: Vector3 = sca_att.scattered_ray.direction;
+                sca_att.attenuation = sca_att.attenuation
+                    * self.through_coat(ray_in, record, &direction)
+                    / (1.0 - reflectance);
+                return true;
+            }
+        }
+
+        // Both the coat and the base could have scattered in that direction, so use the density of the two together
+        let direction: Vector3 = sca_att.scattered_ray.direction;
+        let pdf: f64 = self.scattering_pdf(ray_in, record, &direction);
+        if pdf <= 0.0 {
+            return false;
+        }
+
+        sca_att.attenuation = self.evaluate(ray_in, record, &direction) / pdf;
+        sca_att.pdf = pdf;
+
+        true
+    }
+
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let base: Color = self.base.evaluate(ray_in, record, direction);
+        if !record.front_face {
+            return base;
+        }
+
+        let ggx: Ggx = self.distribution(record);
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+        let wi: Vector3 = frame.to_local(&unit_vector(*direction));
+
+        let coat: f64 = if ggx.is_smooth() || wo.z <= 0.0 || wi.z <= 0.0 {
+            0.0
+        } else {
+            let h: Vector3 = unit_vector(wo + wi);
+            fresnel_dielectric(dot(&wo, &h), self.eta(ray_in))
+                * ggx.distribution(&h)
+                * ggx.masking_shadowing(&wo, &wi)
+                / (4.0 * wo.z)
+        };
+
+        Color::white() * coat + base * self.through_coat(ray_in, record, direction)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let base: f64 = self.base.scattering_pdf(ray_in, record, direction);
+        if !record.front_face {
+            return base;
+        }
+
+        let ggx: Ggx = self.distribution(record);
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+        let wi: Vector3 = frame.to_local(&unit_vector(*direction));
+
+        let reflectance: f64 = fresnel_dielectric(wo.z, self.eta(ray_in));
+        let coat: f64 = if ggx.is_smooth() {
+            0.0
+        } else {
+            ggx.reflection_pdf(&wo, &wi)
+        };
+
+        reflectance * coat + (1.0 - reflectance) * base
+    }
+
+    fn emitted(&self, record: &HitRecord) -> Color {
+        self.base.emitted(record)
+    }
+
+    fn emitted_spectrum(&self, record: &HitRecord, wavelength: f64) -> f64 {
+        self.base.emitted_spectrum(record, wavelength)
+    }
+
+    fn opacity(&self, surface_coords: SurfaceCoordinate, p: &Point3) -> f64 {
+        self.base.opacity(surface_coords, p)
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use crate::hittable::HitRecord;
+use crate::material::microfacet::{Ggx, LocalFrame, fresnel_conductor};
+use crate::material::{Material, ScatteredRayAndAttenuation};
+use crate::point3::color::Color;
+use crate::point3::spectrum::interpolate_rgb_samples;
+use crate::point3::{Vector3, dot, unit_vector};
+use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
+
+/// A physically based metal: a rough surface made of GGX microfacets that reflect according to the Fresnel equations
+/// of a conductor. Unlike Metal it conserves energy and never scatters below the surface.
+/// The color comes from the complex refractive index (eta + i k), given for the red, green and blue wavelengths
+pub struct Conductor {
+    pub eta: Color,
+    pub k: Color,
+    /// Perceptual roughness, from 0 (mirror) to 1. Only the luminance of the texture is used
+    pub roughness: Arc<dyn Texture>,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Arc<Conductor> {
+        Conductor::with_roughness_texture(
+            eta,
+            k,
+            SolidColor::new(Color::new(roughness, roughness, roughness)),
+        )
+    }
+
+    /// A conductor whose roughness changes over the surface, for example scratches or fingerprints
+    pub fn with_roughness_texture(
+        eta: Color,
+        k: Color,
+        roughness: Arc<dyn Texture>,
+    ) -> Arc<Conductor> {
+        Arc::new(Conductor { eta, k, roughness })
+    }
+
+    // Presets, with the refractive indexes at 650, 550 and 450 nm
+
+    pub fn gold(roughness: f64) -> Arc<Conductor> {
+        Conductor::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Arc<Conductor> {
+        Conductor::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Arc<Conductor> {
+        Conductor::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Arc<Conductor> {
+        Conductor::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    /// Fresnel reflectance for the wavelength of ray_in if it carries one, or for red, green and blue otherwise
+    fn fresnel(&self, cos_theta: f64, ray_in: &Ray) -> Color {
+        match ray_in.wavelength {
+            Some(wavelength) => {
+                let eta: f64 = interpolate_rgb_samples(&self.eta, wavelength);
+                let k: f64 = interpolate_rgb_samples(&self.k, wavelength);
+                fresnel_conductor(cos_theta, &Color::new(eta, eta, eta), &Color::new(k, k, k))
+            }
+            None => fresnel_conductor(cos_theta, &self.eta, &self.k),
+        }
+    }
+
+    fn distribution(&self, record: &HitRecord) -> Ggx {
+        Ggx::from_roughness(self.roughness.value_at(record).luminance())
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        let ggx: Ggx = self.distribution(record);
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+
+        if wo.z <= 0.0 {
+            return false;
+        }
+
+        if ggx.is_smooth() {
+            // A perfect mirror: there is only one possible direction
+            let wi: Vector3 = Vector3::new(-wo.x, -wo.y, wo.z);
+            sca_att.scattered_ray = Ray::new(record.p, frame.to_world(&wi));
+            sca_att.attenuation = self.fresnel(wo.z, ray_in);
+            sca_att.pdf = 0.0;
+            return true;
+        }
+
+        let h: Vector3 = ggx.sample_visible_normal(
+            &wo,
+            rand::random_range(0.0..1.0),
+            rand::random_range(0.0..1.0),
+        );
+        let wo_dot_h: f64 = dot(&wo, &h);
+        let wi: Vector3 = 2.0 * wo_dot_h * h - wo;
+
+        // The ray bounced off a microfacet into the surface. Multiple scattering between microfacets is not modelled
+        if wi.z <= 0.0 {
+            return false;
+        }
+
+        sca_att.scattered_ray = Ray::new(record.p, frame.to_world(&wi));
+        // The distribution and the masking of wo cancel out with the pdf of the visible normals
+        sca_att.attenuation =
+            self.fresnel(wo_dot_h, ray_in) * (ggx.masking_shadowing(&wo, &wi) / ggx.masking(&wo));
+        sca_att.pdf = ggx.visible_normal_pdf(&wo, &h) / (4.0 * wo_dot_h);
+
+        true
+    }
+
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let ggx: Ggx = self.distribution(record);
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+        let wi: Vector3 = frame.to_local(&unit_vector(*direction));
+
+        if ggx.is_smooth() || wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::black();
+        }
+
+        let h: Vector3 = unit_vector(wo + wi);
+
+        self.fresnel(dot(&wo, &h), ray_in)
+            * (ggx.distribution(&h) * ggx.masking_shadowing(&wo, &wi) / (4.0 * wo.z))
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let ggx: Ggx = self.distribution(record);
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+        let wi: Vector3 = frame.to_local(&unit_vector(*direction));
+
+        if ggx.is_smooth() {
+            return 0.0;
+        }
+
+        ggx.reflection_pdf(&wo, &wi)
+    }
+}
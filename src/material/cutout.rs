@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use crate::hittable::{HitRecord, SurfaceCoordinate};
+use crate::material::{Material, ScatteredRayAndAttenuation};
+use crate::point3::color::Color;
+use crate::point3::{Point3, Vector3};
+use crate::ray::Ray;
+use crate::texture::{ImageTexture, Texture};
+
+/// A material with holes, for leaves, fences or decals modelled as textured quads. Where the opacity texture is
+/// black rays go through the surface as if it was not there, where it is white they hit the material and in between
+/// a fraction of them goes through. Only the luminance of the texture is used
+pub struct Cutout {
+    pub material: Arc<dyn Material>,
+    pub opacity: Arc<dyn Texture>,
+}
+
+impl Cutout {
+    pub fn new(material: Arc<dyn Material>, opacity: Arc<dyn Texture>) -> Arc<Cutout> {
+        Arc::new(Cutout { material, opacity })
+    }
+
+    /// Use the alpha channel of an image (like a png of a leaf) as the opacity
+    pub fn from_image_alpha(material: Arc<dyn Material>, path: &str) -> Arc<Cutout> {
+        Cutout::new(material, ImageTexture::alpha_or_fallback(path))
+    }
+}
+
+impl Material for Cutout {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        self.material.scatter(ray_in, record, sca_att)
+    }
+
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        self.material.evaluate(ray_in, record, direction)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        self.material.scattering_pdf(ray_in, record, direction)
+    }
+
+    fn emitted(&self, record: &HitRecord) -> Color {
+        self.material.emitted(record)
+    }
+
+    fn emitted_spectrum(&self, record: &HitRecord, wavelength: f64) -> f64 {
+        self.material.emitted_spectrum(record, wavelength)
+    }
+
+    fn opacity(&self, surface_coords: SurfaceCoordinate, p: &Point3) -> f64 {
+        self.opacity.value(surface_coords, p).luminance() * self.material.opacity(surface_coords, p)
+    }
+}
@@ -0,0 +1,75 @@
+// Refractive indexes that depend on the wavelength, which is what splits white light into a rainbow in prisms.
+// The coefficients of the presets come from https://refractiveindex.info
+
+/// A formula for the refractive index as a function of the wavelength. Both use the wavelength in micrometers
+#[derive(Clone, Copy)]
+pub enum Dispersion {
+    /// n = a + b / λ². Simple, good enough for most glasses in the visible range
+    Cauchy { a: f64, b: f64 },
+    /// n² = 1 + Σ b_i λ² / (λ² - c_i). The formula used by glass manufacturers, c is in µm²
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    /// Refractive index for a wavelength in nanometers
+    pub fn refraction_index(&self, wavelength: f64) -> f64 {
+        let lambda: f64 = wavelength * 1e-3;
+        let lambda_squared: f64 = lambda * lambda;
+
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / lambda_squared,
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f64 = b
+                    .iter()
+                    .zip(c.iter())
+                    .map(|(b_i, c_i)| b_i * lambda_squared / (lambda_squared - c_i))
+                    .sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+
+    /// Cauchy's formula for a glass given its refractive index at 587.6 nm (yellow) and its Abbe number,
+    /// which is how glass catalogues describe dispersion. Lower Abbe numbers disperse more
+    pub fn from_abbe_number(refraction_index: f64, abbe_number: f64) -> Dispersion {
+        // Wavelengths of the F, d and C Fraunhofer lines, in micrometers
+        let inverse_squared = |lambda: f64| -> f64 { 1.0 / (lambda * lambda) };
+        let b: f64 = (refraction_index - 1.0)
+            / (abbe_number * (inverse_squared(0.4861) - inverse_squared(0.6563)));
+
+        Dispersion::Cauchy {
+            a: refraction_index - b * inverse_squared(0.5876),
+            b,
+        }
+    }
+
+    /// Schott N-BK7, the most common optical glass
+    pub fn bk7() -> Dispersion {
+        Dispersion::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        }
+    }
+
+    /// Schott SF11, a dense flint glass that disperses a lot, used in prisms
+    pub fn dense_flint() -> Dispersion {
+        Dispersion::Sellmeier {
+            b: [1.73759695, 0.313747346, 1.89878101],
+            c: [0.013188707, 0.0623068142, 155.23629],
+        }
+    }
+
+    pub fn fused_silica() -> Dispersion {
+        Dispersion::Sellmeier {
+            b: [0.6961663, 0.4079426, 0.8974794],
+            c: [0.00467914826, 0.0135120631, 97.9340025],
+        }
+    }
+
+    pub fn diamond() -> Dispersion {
+        Dispersion::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.030625, 0.011236, 0.0],
+        }
+    }
+}
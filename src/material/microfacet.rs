@@ -0,0 +1,326 @@
+// Building blocks for materials whose surface is modelled as many tiny perfect mirrors (microfacets).
+// Resources:
+// - Microfacet models for refraction through rough surfaces, Walter et al. (2007): https://www.cs.cornell.edu/~srm/publications/EGSR07-btdf.pdf
+// - Sampling the GGX distribution of visible normals, Heitz (2018): https://jcgt.org/published/0007/04/01/
+
+use std::f64::consts::PI;
+
+use crate::point3::color::Color;
+use crate::point3::{Point3, Vector3, cross, dot, orthonormal_basis, unit_vector};
+
+/// Below this alpha a surface is treated as a perfect mirror, since the distribution becomes too sharp to evaluate
+pub const SMOOTH_ALPHA: f64 = 1e-3;
+
+/// An orthonormal basis around a normal, so that directions can be expressed in a frame where the normal is +z
+pub struct LocalFrame {
+    tangent: Vector3,
+    bitangent: Vector3,
+    normal: Vector3,
+}
+
+impl LocalFrame {
+    /// normal must be a unit vector
+    pub fn new(normal: &Vector3) -> LocalFrame {
+        let (tangent, bitangent) = orthonormal_basis(normal);
+
+        LocalFrame {
+            tangent,
+            bitangent,
+            normal: *normal,
+        }
+    }
+
+    pub fn to_local(&self, v: &Vector3) -> Vector3 {
+        Point3::new(
+            dot(v, &self.tangent),
+            dot(v, &self.bitangent),
+            dot(v, &self.normal),
+        )
+    }
+
+    pub fn to_world(&self, v: &Vector3) -> Vector3 {
+        v.x * self.tangent + v.y * self.bitangent + v.z * self.normal
+    }
+}
+
+/// The GGX (or Trowbridge-Reitz) distribution of microfacet normals with Smith's masking-shadowing.
+/// All the directions are in the local frame of the surface (normal along +z) and point away from it
+pub struct Ggx {
+    pub alpha: f64,
+}
+
+impl Ggx {
+    /// Perceptual roughness goes from 0 (mirror) to 1 (very rough). Squaring it makes the changes in appearance uniform
+    pub fn from_roughness(roughness: f64) -> Ggx {
+        let roughness: f64 = roughness.clamp(0.0, 1.0);
+        Ggx {
+            alpha: roughness * roughness,
+        }
+    }
+
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < SMOOTH_ALPHA
+    }
+
+    /// Density of microfacets with normal h, per unit solid angle and projected onto the macro surface
+    pub fn distribution(&self, h: &Vector3) -> f64 {
+        if h.z <= 0.0 {
+            return 0.0;
+        }
+        let alpha_squared: f64 = self.alpha * self.alpha;
+        let denominator: f64 = h.z * h.z * (alpha_squared - 1.0) + 1.0;
+
+        alpha_squared / (PI * denominator * denominator)
+    }
+
+    /// Smith's auxiliary function: ratio of the hidden to the visible projected area of the microfacets seen from w
+    fn lambda(&self, w: &Vector3) -> f64 {
+        let cos_squared: f64 = w.z * w.z;
+        if cos_squared == 0.0 {
+            return f64::INFINITY;
+        }
+        let tan_squared: f64 = (1.0 - cos_squared).max(0.0) / cos_squared;
+
+        0.5 * ((1.0 + self.alpha * self.alpha * tan_squared).sqrt() - 1.0)
+    }
+
+    /// Fraction of the microfacets visible from w
+    pub fn masking(&self, w: &Vector3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Fraction of the microfacets visible from both wo and wi (height correlated form)
+    pub fn masking_shadowing(&self, wo: &Vector3, wi: &Vector3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Sample a microfacet normal among the ones visible from wo, which must be above the surface.
+    /// u1 and u2 are uniform random numbers in 0..1
+    pub fn sample_visible_normal(&self, wo: &Vector3, u1: f64, u2: f64) -> Vector3 {
+        // Stretch the view direction so that the distribution becomes the hemisphere of alpha = 1
+        let stretched: Vector3 = Point3::new(self.alpha * wo.x, self.alpha * wo.y, wo.z);
+        let vh: Vector3 = stretched / stretched.length();
+
+        let length_squared: f64 = vh.x * vh.x + vh.y * vh.y;
+        let t1: Vector3 = if length_squared > 0.0 {
+            Point3::new(-vh.y, vh.x, 0.0) / length_squared.sqrt()
+        } else {
+            Point3::new(1.0, 0.0, 0.0)
+        };
+        let t2: Vector3 = cross(&vh, &t1);
+
+        // Uniform point on a disk, warped towards the part of the hemisphere that is visible
+        let r: f64 = u1.sqrt();
+        let phi: f64 = 2.0 * PI * u2;
+        let p1: f64 = r * phi.cos();
+        let s: f64 = 0.5 * (1.0 + vh.z);
+        let p2: f64 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+
+        let nh: Vector3 = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+
+        // Unstretch
+        let h: Vector3 = Point3::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(1e-6));
+        h / h.length()
+    }
+
+    /// Probability density of sample_visible_normal choosing h when seen from wo
+    pub fn visible_normal_pdf(&self, wo: &Vector3, h: &Vector3) -> f64 {
+        if wo.z <= 0.0 {
+            return 0.0;
+        }
+        self.masking(wo) * dot(wo, h).max(0.0) * self.distribution(h) / wo.z
+    }
+
+    /// Reflect wo on a visible microfacet. None if it ends up below the surface
+    pub fn sample_reflection(&self, wo: &Vector3) -> Option<Vector3> {
+        let h: Vector3 = self.sample_visible_normal(
+            wo,
+            rand::random_range(0.0..1.0),
+            rand::random_range(0.0..1.0),
+        );
+        let wi: Vector3 = 2.0 * dot(wo, &h) * h - *wo;
+
+        if wi.z <= 0.0 { None } else { Some(wi) }
+    }
+
+    /// Probability density of sample_reflection choosing wi
+    pub fn reflection_pdf(&self, wo: &Vector3, wi: &Vector3) -> f64 {
+        if wi.z <= 0.0 {
+            return 0.0;
+        }
+        let h: Vector3 = unit_vector(*wo + *wi);
+        let wo_dot_h: f64 = dot(wo, &h);
+        if wo_dot_h <= 0.0 {
+            return 0.0;
+        }
+
+        self.visible_normal_pdf(wo, &h) / (4.0 * wo_dot_h)
+    }
+
+    /// Reflect or refract wo on a visible microfacet of the boundary between two dielectrics, choosing between them
+    /// with the probability given by the Fresnel equations. eta is the refractive index of the side wo is not in
+    /// over the one it is in. A smooth surface only has one microfacet, the surface itself
+    pub fn sample_dielectric(&self, wo: &Vector3, eta: f64) -> Option<Vector3> {
+        let h: Vector3 = if self.is_smooth() {
+            Point3::new(0.0, 0.0, 1.0)
+        } else {
+            self.sample_visible_normal(
+                wo,
+                rand::random_range(0.0..1.0),
+                rand::random_range(0.0..1.0),
+            )
+        };
+        let wo_dot_h: f64 = dot(wo, &h);
+
+        // Total internal reflection always reflects
+        let reflects: bool = rand::random_range(0.0..1.0) < fresnel_dielectric(wo_dot_h, eta);
+
+        let wi: Vector3 = if reflects {
+            2.0 * wo_dot_h * h - *wo
+        } else {
+            refract_through(wo, &h, eta)?
+        };
+
+        // The microfacet sent the ray to the wrong side of the surface
+        if reflects != (wi.z > 0.0) {
+            return None;
+        }
+        Some(wi)
+    }
+
+    /// The scattering function of a rough dielectric boundary times the cosine of wi (Walter et al.),
+    /// both for reflection (wi above the surface) and refraction (wi below it)
+    pub fn evaluate_dielectric(&self, wo: &Vector3, wi: &Vector3, eta: f64) -> f64 {
+        if self.is_smooth() || wo.z <= 0.0 || wi.z == 0.0 {
+            return 0.0;
+        }
+
+        let h: Vector3 = dielectric_half_vector(wo, wi, eta);
+        let wo_dot_h: f64 = dot(wo, &h);
+        let wi_dot_h: f64 = dot(wi, &h);
+        let fresnel: f64 = fresnel_dielectric(wo_dot_h, eta);
+        let microfacets: f64 = self.distribution(&h) * self.masking_shadowing(wo, wi);
+
+        if wi.z > 0.0 {
+            return fresnel * microfacets / (4.0 * wo.z);
+        }
+
+        // Refraction is only possible if wo and wi are on opposite sides of the microfacet
+        if wo_dot_h <= 0.0 || wi_dot_h >= 0.0 {
+            return 0.0;
+        }
+        let denominator: f64 = wo_dot_h + eta * wi_dot_h;
+
+        wo_dot_h * -wi_dot_h * eta * eta * (1.0 - fresnel) * microfacets
+            / (wo.z * denominator * denominator)
+    }
+
+    /// Probability density of sample_dielectric choosing wi
+    pub fn dielectric_pdf(&self, wo: &Vector3, wi: &Vector3, eta: f64) -> f64 {
+        if self.is_smooth() || wo.z <= 0.0 || wi.z == 0.0 {
+            return 0.0;
+        }
+
+        let h: Vector3 = dielectric_half_vector(wo, wi, eta);
+        let wo_dot_h: f64 = dot(wo, &h);
+        let wi_dot_h: f64 = dot(wi, &h);
+        if wo_dot_h <= 0.0 {
+            return 0.0;
+        }
+        let fresnel: f64 = fresnel_dielectric(wo_dot_h, eta);
+        let normal_pdf: f64 = self.visible_normal_pdf(wo, &h);
+
+        if wi.z > 0.0 {
+            return fresnel * normal_pdf / (4.0 * wo_dot_h);
+        }
+
+        if wi_dot_h >= 0.0 {
+            return 0.0;
+        }
+        // Jacobian of the refraction, going from microfacet normals to refracted directions
+        let denominator: f64 = wo_dot_h + eta * wi_dot_h;
+        (1.0 - fresnel) * normal_pdf * eta * eta * -wi_dot_h / (denominator * denominator)
+    }
+}
+
+/// Microfacet normal (facing the side of wo) that takes wo to wi, for reflection and refraction
+fn dielectric_half_vector(wo: &Vector3, wi: &Vector3, eta: f64) -> Vector3 {
+    let h: Vector3 = if wi.z > 0.0 {
+        unit_vector(*wo + *wi)
+    } else {
+        -unit_vector(*wo + eta * *wi)
+    };
+
+    if h.z < 0.0 { -h } else { h }
+}
+
+/// Schlick's approximation of the Fresnel reflectance, given the reflectance at normal incidence
+pub fn fresnel_schlick(cos_theta: f64, f0: &Color) -> Color {
+    let weight: f64 = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    *f0 + (Color::white() - *f0) * weight
+}
+
+/// Fresnel reflectance of a conductor with complex refractive index eta + i k (one value per color channel),
+/// for light arriving at an angle with cosine cos_theta from the normal
+pub fn fresnel_conductor(cos_theta: f64, eta: &Color, k: &Color) -> Color {
+    let cos_theta: f64 = cos_theta.clamp(0.0, 1.0);
+
+    let channel = |eta: f64, k: f64| -> f64 {
+        let cos_squared: f64 = cos_theta * cos_theta;
+        let sin_squared: f64 = 1.0 - cos_squared;
+        let eta_squared: f64 = eta * eta;
+        let k_squared: f64 = k * k;
+
+        let t0: f64 = eta_squared - k_squared - sin_squared;
+        let a_squared_plus_b_squared: f64 = (t0 * t0 + 4.0 * eta_squared * k_squared).sqrt();
+        let a: f64 = (0.5 * (a_squared_plus_b_squared + t0)).max(0.0).sqrt();
+
+        let t1: f64 = a_squared_plus_b_squared + cos_squared;
+        let t2: f64 = 2.0 * cos_theta * a;
+        let r_s: f64 = (t1 - t2) / (t1 + t2);
+
+        let t3: f64 = cos_squared * a_squared_plus_b_squared + sin_squared * sin_squared;
+        let t4: f64 = t2 * sin_squared;
+        let r_p: f64 = r_s * (t3 - t4) / (t3 + t4);
+
+        0.5 * (r_s + r_p)
+    };
+
+    Color::new(
+        channel(eta.x, k.x),
+        channel(eta.y, k.y),
+        channel(eta.z, k.z),
+    )
+}
+
+/// Fresnel reflectance of the boundary between two dielectrics, for light arriving at an angle with cosine cos_theta
+/// from the normal. eta is the refractive index of the side the light goes into over the one it comes from.
+/// Returns 1.0 when there is total internal reflection
+pub fn fresnel_dielectric(cos_theta: f64, eta: f64) -> f64 {
+    let cos_i: f64 = cos_theta.clamp(0.0, 1.0);
+    let sin_squared_t: f64 = (1.0 - cos_i * cos_i) / (eta * eta);
+
+    if sin_squared_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t: f64 = (1.0 - sin_squared_t).sqrt();
+
+    let r_s: f64 = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let r_p: f64 = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+
+    0.5 * (r_s * r_s + r_p * r_p)
+}
+
+/// Refract the unit vector wo (pointing away from the surface) through the microfacet normal h, with eta the
+/// refractive index of the side the light goes into over the one it comes from. None if there is total internal reflection
+pub fn refract_through(wo: &Vector3, h: &Vector3, eta: f64) -> Option<Vector3> {
+    let cos_i: f64 = dot(wo, h);
+    let sin_squared_t: f64 = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+
+    if sin_squared_t >= 1.0 {
+        return None;
+    }
+    let cos_t: f64 = (1.0 - sin_squared_t).sqrt();
+
+    Some(-*wo / eta + (cos_i / eta - cos_t) * *h)
+}
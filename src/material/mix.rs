@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use crate::hittable::{HitRecord, SurfaceCoordinate};
+use crate::material::{Material, ScatteredRayAndAttenuation};
+use crate::point3::color::Color;
+use crate::point3::{Point3, Vector3};
+use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
+
+/// A blend of two materials, like rust over a metal or moss over a rock. At each hit one of them is chosen at
+/// random, with the probability of second given by the weight. Only the luminance of the weight texture is used,
+/// so a black and white image works as a mask
+pub struct MixMaterial {
+    pub first: Arc<dyn Material>,
+    pub second: Arc<dyn Material>,
+    pub weight: Arc<dyn Texture>,
+}
+
+impl MixMaterial {
+    /// Mix with the same weight everywhere. A weight of 0 is only first and 1 is only second
+    pub fn new(
+        first: Arc<dyn Material>,
+        second: Arc<dyn Material>,
+        weight: f64,
+    ) -> Arc<MixMaterial> {
+        MixMaterial::with_mask(
+            first,
+            second,
+            SolidColor::new(Color::new(weight, weight, weight)),
+        )
+    }
+
+    /// Mix following a texture: second is used where the mask is white and first where it is black
+    pub fn with_mask(
+        first: Arc<dyn Material>,
+        second: Arc<dyn Material>,
+        mask: Arc<dyn Texture>,
+    ) -> Arc<MixMaterial> {
+        Arc::new(MixMaterial {
+            first,
+            second,
+            weight: mask,
+        })
+    }
+
+    fn weight(&self, surface_coords: SurfaceCoordinate, p: &Point3) -> f64 {
+        self.weight
+            .value(surface_coords, p)
+            .luminance()
+            .clamp(0.0, 1.0)
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        let weight: f64 = self.weight(record.surface_coords, &record.p);
+        let chosen: &Arc<dyn Material> = if rand::random_range(0.0..1.0) < weight {
+            &self.second
+        } else {
+            &self.first
+        };
+
+        if !chosen.scatter(ray_in, record, sca_att) {
+            return false;
+        }
+
+        // A specular bounce can only come from the chosen material, whose probability cancels out with its weight.
+        // Otherwise both materials could have scattered in that direction, so use the density of the mix
+        if sca_att.pdf > 0.0 {
+            let direction: Vector3 = sca_att.scattered_ray.direction;
+            let pdf: f64 = self.scattering_pdf(ray_in, record, &direction);
+            if pdf <= 0.0 {
+                return false;
+            }
+
+            sca_att.attenuation = self.evaluate(ray_in, record, &direction) / pdf;
+            sca_att.pdf = pdf;
+        }
+
+        true
+    }
+
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let weight: f64 = self.weight(record.surface_coords, &record.p);
+
+        (1.0 - weight) * self.first.evaluate(ray_in, record, direction)
+            + weight * self.second.evaluate(ray_in, record, direction)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let weight: f64 = self.weight(record.surface_coords, &record.p);
+
+        (1.0 - weight) * self.first.scattering_pdf(ray_in, record, direction)
+            + weight * self.second.scattering_pdf(ray_in, record, direction)
+    }
+
+    fn emitted(&self, record: &HitRecord) -> Color {
+        let weight: f64 = self.weight(record.surface_coords, &record.p);
+
+        (1.0 - weight) * self.first.emitted(record) + weight * self.second.emitted(record)
+    }
+
+    fn emitted_spectrum(&self, record: &HitRecord, wavelength: f64) -> f64 {
+        let weight: f64 = self.weight(record.surface_coords, &record.p);
+
+        (1.0 - weight) * self.first.emitted_spectrum(record, wavelength)
+            + weight * self.second.emitted_spectrum(record, wavelength)
+    }
+
+    fn opacity(&self, surface_coords: SurfaceCoordinate, p: &Point3) -> f64 {
+        let weight: f64 = self.weight(surface_coords, p);
+
+        (1.0 - weight) * self.first.opacity(surface_coords, p)
+            + weight * self.second.opacity(surface_coords, p)
+    }
+}
@@ -0,0 +1,727 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::hittable::heterogeneous_medium::DensityField;
+use crate::hittable::{HitRecord, SurfaceCoordinate};
+use crate::material::dispersion::Dispersion;
+use crate::point3::color::Color;
+use crate::point3::spectrum::{
+    blackbody_color, blackbody_relative_luminance, rgb_to_spectrum, sample_wavelength,
+    spectrum_to_xyz, wavelength_weight, white_balanced_planck,
+};
+use crate::point3::{
+    Point3, Vector3, dot, orthonormal_basis, random_unit_vector, reflect, refract, unit_vector,
+};
+use crate::ray::{MediumEntry, MediumStack, Ray, next_medium_id};
+use crate::texture::{SolidColor, Texture};
+
+// If you are confused about the lifetimes, think about it this way:
+// multiple objects could use the same material, which means that the material pointer needs to outlive everything else
+pub struct ScatteredRayAndAttenuation {
+    // think of a better name?
+    pub scattered_ray: Ray,
+    pub attenuation: Color,
+    /// Probability density (per unit solid angle) of the scattered direction. Zero if the scattering is
+    /// specular (the direction could not have been anything else), in which case lights are not sampled directly
+    pub pdf: f64,
+}
+
+/// The default for scatter is ScatterResult::DidNotScatter and for emitted Point3 { x: 0.0, y: 0.0, z: 0.0 } (black).
+/// Materials that set a non zero pdf when scattering should also implement evaluate and scattering_pdf, so that the
+/// integrator can ask them about directions it chose itself, like the ones towards lights
+pub trait Material: Sync + Send {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        _record: &HitRecord,
+        _sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        false
+    }
+
+    /// The scattering function times the cosine between direction and the normal: how much of the light arriving
+    /// along direction leaves along -ray_in.direction
+    fn evaluate(&self, _ray_in: &Ray, _record: &HitRecord, _direction: &Vector3) -> Color {
+        Color::black()
+    }
+
+    /// Probability density (per unit solid angle) with which scatter would have chosen direction
+    fn scattering_pdf(&self, _ray_in: &Ray, _record: &HitRecord, _direction: &Vector3) -> f64 {
+        0.0
+    }
+
+    fn emitted(&self, _record: &HitRecord) -> Color {
+        Color::black()
+    }
+
+    /// Probability that a ray stops at this point of the surface instead of going through it. Less than one where
+    /// the surface is cut out, like the gaps between the leaves of a textured quad. Checked by the hit functions
+    fn opacity(&self, _surface_coords: SurfaceCoordinate, _p: &Point3) -> f64 {
+        1.0
+    }
+
+    /// Emitted radiance at a single wavelength (in nanometers), used when a path carries only one wavelength.
+    /// By default it is the emitted color turned into a spectrum
+    fn emitted_spectrum(&self, record: &HitRecord, wavelength: f64) -> f64 {
+        rgb_to_spectrum(&self.emitted(record), wavelength)
+    }
+}
+
+// Resources:
+// - http://www.vendian.org/mncharity/dir3/blackbody/
+// - https://web.archive.org/web/20010821031240/http://astronomy.swin.edu.au:80/pbourke/colour/conversion.html
+/// A perfect black body: absorbs all incoming rays and emits light following Planck's law for its temperature.
+/// The spectrum is integrated against the CIE color matching functions to get its color
+pub struct BlackBody {
+    /// Temperature in Kelvin
+    pub temperature: f64,
+    /// Emitted radiance, already multiplied by the intensity
+    color: Color,
+    /// Converts Planck's law to the same brightness as color
+    spectral_scale: f64,
+}
+
+impl BlackBody {
+    /// Create a black body at temperature (in Kelvin). If normalize is true, intensity is its luminance, so bodies of
+    /// different temperatures look equally bright. Otherwise intensity is the luminance of a body at 6500 K and hotter
+    /// bodies are brighter, as they are in reality
+    pub fn new(temperature: f64, intensity: f64, normalize: bool) -> Arc<BlackBody> {
+        let luminance: f64 = if normalize {
+            intensity
+        } else {
+            intensity * blackbody_relative_luminance(temperature)
+        };
+
+        // A flat spectrum of value 1 is white with luminance 1
+        let flat_luminance: f64 = spectrum_to_xyz(|_| 1.0).y;
+        let planck_luminance: f64 =
+            spectrum_to_xyz(|wavelength| white_balanced_planck(wavelength, temperature)).y;
+
+        Arc::new(BlackBody {
+            temperature,
+            color: luminance * blackbody_color(temperature),
+            spectral_scale: if planck_luminance > 0.0 {
+                luminance * flat_luminance / planck_luminance
+            } else {
+                0.0
+            },
+        })
+    }
+}
+
+impl Material for BlackBody {
+    fn emitted(&self, _record: &HitRecord) -> Color {
+        self.color
+    }
+
+    fn emitted_spectrum(&self, _record: &HitRecord, wavelength: f64) -> f64 {
+        self.spectral_scale * white_balanced_planck(wavelength, self.temperature)
+    }
+}
+
+/// Hottest temperature (in Kelvin) that BlackBodyMedium tells apart, and the step of its table of colors
+const MEDIUM_MAX_TEMPERATURE: f64 = 12000.0;
+const MEDIUM_TEMPERATURE_STEP: f64 = 50.0;
+
+/// Phase function for a medium that glows with its temperature, like fire or an explosion. The temperature comes from
+/// a field (like a GridField with the temperatures of a simulation) and each point emits like a black body, scaled by
+/// intensity. albedo is the fraction of the light that gets scattered (isotropically) instead of absorbed, and only
+/// the absorbed part emits
+pub struct BlackBodyMedium {
+    pub albedo: Color,
+    pub temperature: Arc<dyn DensityField>,
+    /// Radiance at every multiple of MEDIUM_TEMPERATURE_STEP, as the spectrum is too slow to integrate at each hit
+    radiance: Vec<Color>,
+}
+
+impl BlackBodyMedium {
+    /// intensity is the luminance of a body at 6500 K, as in BlackBody
+    pub fn new(
+        albedo: Color,
+        temperature: Arc<dyn DensityField>,
+        intensity: f64,
+    ) -> Arc<BlackBodyMedium> {
+        let steps: usize = (MEDIUM_MAX_TEMPERATURE / MEDIUM_TEMPERATURE_STEP) as usize;
+        let radiance: Vec<Color> = (0..=steps)
+            .map(|i| {
+                let temperature: f64 = i as f64 * MEDIUM_TEMPERATURE_STEP;
+                intensity * blackbody_relative_luminance(temperature) * blackbody_color(temperature)
+            })
+            .collect();
+
+        Arc::new(BlackBodyMedium {
+            albedo,
+            temperature,
+            radiance,
+        })
+    }
+
+    fn radiance_at(&self, temperature: f64) -> Color {
+        let position: f64 =
+            (temperature / MEDIUM_TEMPERATURE_STEP).clamp(0.0, (self.radiance.len() - 1) as f64);
+        let before: usize = position.floor() as usize;
+        let after: usize = (before + 1).min(self.radiance.len() - 1);
+        let t: f64 = position - before as f64;
+
+        (1.0 - t) * self.radiance[before] + t * self.radiance[after]
+    }
+}
+
+impl Material for BlackBodyMedium {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        sca_att.scattered_ray = Ray::new(record.p, random_unit_vector());
+        sca_att.attenuation = self.albedo;
+        sca_att.pdf = 1.0 / (4.0 * PI);
+
+        true
+    }
+
+    fn evaluate(&self, _ray_in: &Ray, _record: &HitRecord, _direction: &Vector3) -> Color {
+        self.albedo / (4.0 * PI)
+    }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, _record: &HitRecord, _direction: &Vector3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn emitted(&self, record: &HitRecord) -> Color {
+        (Color::white() - self.albedo) * self.radiance_at(self.temperature.density(&record.p))
+    }
+}
+
+/// A Lambertian or ideal diffuse material.  
+pub struct Lambertian {
+    pub texture: Arc<dyn Texture>,
+}
+
+impl Material for Lambertian {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        // Do it this way to avoid defining a mutable varible
+        let scatter_direction: Point3 = {
+            let temp: Point3 = record.normal + random_unit_vector();
+            // Catch degenerate scatter direction
+            if temp.is_near_zero() {
+                record.normal
+            } else {
+                temp
+            }
+        };
+
+        sca_att.scattered_ray = Ray::new(record.p, scatter_direction);
+        sca_att.attenuation = self.texture.value_at(record);
+        // Adding a random unit vector to the normal gives a cosine distributed direction
+        sca_att.pdf = self.scattering_pdf(ray_in, record, &scatter_direction);
+
+        true
+    }
+
+    fn evaluate(&self, _ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let cos_theta: f64 = dot(&record.normal, &unit_vector(*direction)).max(0.0);
+        self.texture.value_at(record) * (cos_theta / PI)
+    }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        dot(&record.normal, &unit_vector(*direction)).max(0.0) / PI
+    }
+}
+
+impl Lambertian {
+    pub fn from_color(color: Color) -> Arc<Lambertian> {
+        Arc::new(Lambertian {
+            texture: SolidColor::new(color),
+        })
+    }
+
+    pub fn from_texture(texture: Arc<dyn Texture>) -> Arc<Lambertian> {
+        Arc::new(Lambertian { texture })
+    }
+}
+
+/// A metal material: it reflects according to Snell's law, with some randomness added, controled by the fuzz parameter.
+/// Not physically based, see conductor::Conductor for that
+pub struct Metal {
+    pub albedo: Color,
+    pub fuzz: f64,
+}
+
+impl Material for Metal {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        let reflected: Vector3 = reflect(ray_in.direction, record.normal);
+        let reflected_with_fuzz: Vector3 =
+            unit_vector(reflected) + (self.fuzz * random_unit_vector());
+
+        sca_att.scattered_ray = Ray::new(record.p, reflected_with_fuzz);
+        sca_att.attenuation = self.albedo;
+
+        true
+    }
+}
+
+pub fn metal(albedo: Color, fuzz: f64) -> Arc<Metal> {
+    Arc::new(Metal { albedo, fuzz })
+}
+
+/// A dielectric material: it reflets or refracts depending on the angle:
+///     - Reflects: Angle is shallow enough or reflectance is bigger that some random value
+///     - Refracts: otherwise
+/// Reflectance is calculated with Schlick's aproximation. Light travelling inside is absorbed following the
+/// Beer-Lambert law, so thick parts of colored glass look darker than thin ones. The camera applies it to every
+/// part of a path inside, so other objects in the medium (a rock under tinted water) are tinted too.
+/// If it has dispersion the refractive index depends on the wavelength: the first time a path goes through a
+/// dispersive material it picks a single wavelength, which it carries from then on.
+/// Rays keep track of the dielectrics they are inside of, so nested objects (ice in water) use the refractive index
+/// of both sides. Where objects overlap, like water modelled slightly into the walls of its glass, the surfaces of
+/// the one with the lower priority are ignored inside of the other one
+pub struct Dielectric {
+    /// Refractive index in vacuum or air, or the ratio of the material's refractive index over
+    /// the refractive index of the enclosing media. Not used if there is dispersion
+    pub refraction_index: f64,
+    /// Fraction of the light absorbed per unit of distance travelled inside, for each color channel
+    pub absorption: Color,
+    pub dispersion: Option<Dispersion>,
+    /// Which medium wins where it overlaps with others
+    pub priority: u32,
+    /// Identifies the medium in the media of rays. Take a new one from next_medium_id(), and share the material to
+    /// make several objects the same medium
+    pub medium_id: usize,
+}
+
+impl Material for Dielectric {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        // White light gets split: follow a single wavelength, which carries only part of the color
+        let (wavelength, wavelength_color): (Option<f64>, Color) =
+            match (&self.dispersion, ray_in.wavelength) {
+                (Some(_), None) => {
+                    let wavelength: f64 = sample_wavelength();
+                    (Some(wavelength), wavelength_weight(wavelength))
+                }
+                _ => (ray_in.wavelength, Color::white()),
+            };
+
+        let refraction_index: f64 = match (&self.dispersion, wavelength) {
+            (Some(dispersion), Some(wavelength)) => dispersion.refraction_index(wavelength),
+            _ => self.refraction_index,
+        };
+
+        let boundary: MediumBoundary =
+            medium_boundary(self.medium_entry(refraction_index), ray_in, record);
+        if boundary.ignored {
+            return pass_through(
+                ray_in,
+                record,
+                sca_att,
+                boundary.media_after,
+                boundary.transmittance,
+            );
+        }
+        let ratio_indexes: f64 = boundary.index_before / boundary.index_after;
+
+        let unit_direction: Vector3 = unit_vector(ray_in.direction);
+        let cos_theta: f64 = dot(&record.normal, &(-unit_direction)).min(1.0);
+        let sin_theta: f64 = (1.0 - cos_theta * cos_theta).sqrt();
+
+        let cannot_refract: bool = ratio_indexes * sin_theta > 1.0;
+        let reflectance_bigger_than_random: bool =
+            reflectance(cos_theta, ratio_indexes) > rand::random_range(0.0..1.0);
+
+        // Reflected rays stay in the same media, so they share them with ray_in
+        let (direction, media_after): (Vector3, Option<Arc<MediumStack>>) = {
+            if cannot_refract | reflectance_bigger_than_random {
+                (reflect(unit_direction, record.normal), ray_in.media.clone())
+            } else {
+                (
+                    refract(unit_direction, record.normal, ratio_indexes),
+                    Some(Arc::new(boundary.media_after)),
+                )
+            }
+        };
+
+        sca_att.scattered_ray = Ray::new(record.p, direction)
+            .with_wavelength(wavelength)
+            .with_media(media_after);
+        sca_att.attenuation = wavelength_color * boundary.transmittance;
+
+        true
+    }
+}
+
+impl Dielectric {
+    /// A clear dielectric with a tint: light that travels distance inside it gets multiplied by color
+    pub fn tinted(refraction_index: f64, color: Color, distance: f64) -> Arc<Dielectric> {
+        Arc::new(Dielectric {
+            refraction_index,
+            absorption: absorption_from_color(color, distance),
+            dispersion: None,
+            priority: 0,
+            medium_id: next_medium_id(),
+        })
+    }
+
+    /// A clear dielectric whose refractive index changes with the wavelength, like the glass of a prism
+    pub fn dispersive(dispersion: Dispersion) -> Arc<Dielectric> {
+        Arc::new(Dielectric {
+            // Index at 587.6 nm, the usual reference
+            refraction_index: dispersion.refraction_index(587.6),
+            absorption: Color::black(),
+            dispersion: Some(dispersion),
+            priority: 0,
+            medium_id: next_medium_id(),
+        })
+    }
+
+    /// A dielectric that wins over the ones with a lower priority where they overlap. The container of a liquid
+    /// usually gets the biggest one
+    pub fn prioritized(
+        refraction_index: f64,
+        color: Color,
+        distance: f64,
+        priority: u32,
+    ) -> Arc<Dielectric> {
+        Arc::new(Dielectric {
+            refraction_index,
+            absorption: absorption_from_color(color, distance),
+            dispersion: None,
+            priority,
+            medium_id: next_medium_id(),
+        })
+    }
+
+    /// What a ray that goes inside remembers about the material
+    pub fn medium_entry(&self, refraction_index: f64) -> MediumEntry {
+        MediumEntry {
+            id: self.medium_id,
+            priority: self.priority,
+            refraction_index,
+            absorption: self.absorption,
+        }
+    }
+}
+
+/// What a ray finds on each side of the surface of a medium, like glass or water
+pub struct MediumBoundary {
+    /// Refractive index of the side the ray comes from
+    pub index_before: f64,
+    /// Refractive index of the side the ray goes into
+    pub index_after: f64,
+    /// The media of the ray once it goes through the surface
+    pub media_after: MediumStack,
+    /// The surface is inside of a medium that wins over this one, so it is not really there
+    pub ignored: bool,
+    /// Light absorbed by the medium on the way to the surface. Only for a ray that did not know it was inside
+    /// (because it started inside), as the camera absorbs the light in the media that rays know about
+    pub transmittance: Color,
+}
+
+/// The sides of the surface of the medium described by entry that ray_in hits. Materials that rays go into use it,
+/// so that nested and overlapping objects work the same with all of them
+pub fn medium_boundary(entry: MediumEntry, ray_in: &Ray, record: &HitRecord) -> MediumBoundary {
+    let media: MediumStack = ray_in.media.as_deref().copied().unwrap_or_default();
+    let current: Option<MediumEntry> = media.current();
+    let index_of = |medium: Option<MediumEntry>| -> f64 {
+        medium.map_or(1.0, |medium| medium.refraction_index)
+    };
+
+    let transmittance: Color = if current.is_none() {
+        transmittance_inside(&entry.absorption, ray_in, record)
+    } else {
+        Color::white()
+    };
+
+    let mut media_after: MediumStack = media;
+    let (index_before, index_after, ignored): (f64, f64, bool) = if record.front_face {
+        media_after.push(entry);
+        (
+            index_of(current),
+            entry.refraction_index,
+            current.is_some_and(|medium| medium.priority > entry.priority),
+        )
+    } else if media.contains(entry.id) {
+        media_after.remove(entry.id);
+        (
+            entry.refraction_index,
+            index_of(media_after.current()),
+            current.is_some_and(|medium| medium.id != entry.id),
+        )
+    } else {
+        (entry.refraction_index, index_of(current), false)
+    };
+
+    MediumBoundary {
+        index_before,
+        index_after,
+        media_after,
+        ignored,
+        transmittance,
+    }
+}
+
+/// The ray goes on as if the surface was not there, with the media changed
+pub fn pass_through(
+    ray_in: &Ray,
+    record: &HitRecord,
+    sca_att: &mut ScatteredRayAndAttenuation,
+    media: MediumStack,
+    transmittance: Color,
+) -> bool {
+    sca_att.scattered_ray = Ray::new(record.p, ray_in.direction)
+        .with_wavelength(ray_in.wavelength)
+        .with_media(Some(Arc::new(media)));
+    sca_att.attenuation = transmittance;
+    sca_att.pdf = 0.0;
+
+    true
+}
+
+/// Absorption coefficient that leaves color after travelling distance through the material
+pub fn absorption_from_color(color: Color, distance: f64) -> Color {
+    let channel = |c: f64| -> f64 { -c.clamp(1e-6, 1.0).ln() / distance };
+
+    Color::new(channel(color.x), channel(color.y), channel(color.z))
+}
+
+/// Fraction of the light that survives the trip from the origin of ray_in to the hit point (Beer-Lambert law).
+/// A ray that hits the back face of a surface must have been travelling inside the object
+pub fn transmittance_inside(absorption: &Color, ray_in: &Ray, record: &HitRecord) -> Color {
+    if record.front_face {
+        return Color::white();
+    }
+
+    beer_lambert(absorption, record.t * ray_in.direction.length())
+}
+
+/// Fraction of the light that survives travelling distance through a medium with absorption. Channels without
+/// absorption let all of it through, even over an infinite distance
+pub fn beer_lambert(absorption: &Color, distance: f64) -> Color {
+    let channel = |a: f64| -> f64 {
+        if a == 0.0 {
+            return 1.0;
+        }
+        (-a * distance).exp()
+    };
+
+    Color::new(
+        channel(absorption.x),
+        channel(absorption.y),
+        channel(absorption.z),
+    )
+}
+
+fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
+    let r0: f64 = (1.0 - refraction_index) / (1.0 + refraction_index);
+    let r0_squared: f64 = r0 * r0;
+
+    r0_squared + (1.0 - r0_squared) * ((1.0 - cosine).powf(5.0))
+}
+
+pub fn dielectric(refraction_index: f64) -> Arc<Dielectric> {
+    Arc::new(Dielectric {
+        refraction_index,
+        absorption: Color::black(),
+        dispersion: None,
+        priority: 0,
+        medium_id: next_medium_id(),
+    })
+}
+
+// A diffuse light: always emmits some texture, does not scatter
+pub struct DiffuseLight {
+    texture: Arc<dyn Texture>,
+    /// Multiplies the color of the texture, so the texture can stay in the 0..1 range
+    intensity: f64,
+    /// Whether the light is emitted from both faces of the surface or only from the front face
+    two_sided: bool,
+}
+
+impl DiffuseLight {
+    /// Create a light whose emitted radiance is the value of texture times intensity. A one sided light only emits
+    /// from the front face (the side the outward normal points to), like a softbox
+    pub fn new(texture: Arc<dyn Texture>, intensity: f64, two_sided: bool) -> Arc<DiffuseLight> {
+        Arc::new(DiffuseLight {
+            texture,
+            intensity,
+            two_sided,
+        })
+    }
+
+    /// A two sided light of a single color
+    pub fn from_color(color: Point3) -> Arc<DiffuseLight> {
+        DiffuseLight::new(SolidColor::new(color), 1.0, true)
+    }
+
+    /// A two sided light that emits the colors of texture, for example an ImageTexture
+    pub fn from_texture(texture: Arc<dyn Texture>) -> Arc<DiffuseLight> {
+        DiffuseLight::new(texture, 1.0, true)
+    }
+}
+
+impl Material for DiffuseLight {
+    fn emitted(&self, record: &HitRecord) -> Color {
+        if !self.two_sided && !record.front_face {
+            return Color::black();
+        }
+        self.intensity * self.texture.value_at(record)
+    }
+}
+
+/// An isotropic material: Scatters light in a uniform random direction
+pub struct Isotropic {
+    pub texture: Arc<dyn Texture>,
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        // Scatter in a uniform random direction
+        sca_att.scattered_ray = Ray::new(record.p, random_unit_vector());
+        sca_att.attenuation = self.texture.value_at(record);
+        sca_att.pdf = 1.0 / (4.0 * PI);
+
+        true
+    }
+
+    fn evaluate(&self, _ray_in: &Ray, record: &HitRecord, _direction: &Vector3) -> Color {
+        self.texture.value_at(record) / (4.0 * PI)
+    }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, _record: &HitRecord, _direction: &Vector3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+/// The Henyey-Greenstein phase function: scatters light around the direction it was going. With g > 0 most of it
+/// keeps going forward, like in fog or clouds (which gives halos around lights), with g < 0 most of it goes back,
+/// like in dust, and with g = 0 it is isotropic. Optionally a second lobe is mixed in, as real media usually have
+/// a strong forward peak and a weaker backward one
+pub struct HenyeyGreenstein {
+    pub texture: Arc<dyn Texture>,
+    /// Asymmetry of the first lobe, between -1 and 1: the average cosine of the scattering angle
+    pub g: f64,
+    /// Asymmetry of the second lobe
+    pub second_g: f64,
+    /// Fraction of the light scattered by the second lobe
+    pub second_weight: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(texture: Arc<dyn Texture>, g: f64) -> Arc<HenyeyGreenstein> {
+        HenyeyGreenstein::two_lobes(texture, g, 0.0, 0.0)
+    }
+
+    /// A mix of two lobes, the second one with the fraction weight of the light
+    pub fn two_lobes(
+        texture: Arc<dyn Texture>,
+        g: f64,
+        second_g: f64,
+        weight: f64,
+    ) -> Arc<HenyeyGreenstein> {
+        // Very close to 1 the lobe is a delta, which can not be evaluated
+        let limit: f64 = 0.999;
+
+        Arc::new(HenyeyGreenstein {
+            texture,
+            g: g.clamp(-limit, limit),
+            second_g: second_g.clamp(-limit, limit),
+            second_weight: weight.clamp(0.0, 1.0),
+        })
+    }
+
+    /// Density of the scattered direction, given the cosine of the angle between it and the incoming direction
+    fn phase(&self, cos_theta: f64) -> f64 {
+        let lobe = |g: f64| -> f64 {
+            let denominator: f64 = 1.0 + g * g - 2.0 * g * cos_theta;
+            (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+        };
+
+        (1.0 - self.second_weight) * lobe(self.g) + self.second_weight * lobe(self.second_g)
+    }
+}
+
+/// Cosine of the angle between the incoming and the scattered directions, distributed following a
+/// Henyey-Greenstein lobe with asymmetry g
+fn sample_henyey_greenstein(g: f64) -> f64 {
+    let random: f64 = rand::random_range(0.0..1.0);
+    if g.abs() < 1e-3 {
+        return 1.0 - 2.0 * random;
+    }
+
+    let fraction: f64 = (1.0 - g * g) / (1.0 - g + 2.0 * g * random);
+    ((1.0 + g * g - fraction * fraction) / (2.0 * g)).clamp(-1.0, 1.0)
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        let g: f64 = if rand::random_range(0.0..1.0) < self.second_weight {
+            self.second_g
+        } else {
+            self.g
+        };
+        let cos_theta: f64 = sample_henyey_greenstein(g);
+        let sin_theta: f64 = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi: f64 = rand::random_range(0.0..(2.0 * PI));
+
+        let forward: Vector3 = unit_vector(ray_in.direction);
+        let (tangent, bitangent): (Vector3, Vector3) = orthonormal_basis(&forward);
+        let direction: Vector3 = sin_theta * phi.cos() * tangent
+            + sin_theta * phi.sin() * bitangent
+            + cos_theta * forward;
+
+        sca_att.scattered_ray = Ray::new(record.p, direction);
+        // The phase function cancels out with its pdf
+        sca_att.attenuation = self.texture.value_at(record);
+        sca_att.pdf = self.phase(cos_theta);
+
+        true
+    }
+
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        self.texture.value_at(record) * self.scattering_pdf(ray_in, record, direction)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, _record: &HitRecord, direction: &Vector3) -> f64 {
+        self.phase(dot(
+            &unit_vector(ray_in.direction),
+            &unit_vector(*direction),
+        ))
+    }
+}
+
+pub mod coated;
+pub mod conductor;
+pub mod cutout;
+pub mod dispersion;
+pub mod microfacet;
+pub mod mix;
+pub mod normal_map;
+pub mod oren_nayar;
+pub mod principled;
+pub mod rough_dielectric;
+pub mod subsurface;
@@ -0,0 +1,195 @@
+use std::sync::Arc;
+
+use crate::hittable::{HitRecord, SurfaceCoordinate};
+use crate::material::{Material, ScatteredRayAndAttenuation};
+use crate::point3::color::Color;
+use crate::point3::{Point3, Vector3, cross, dot, orthonormal_basis, unit_vector};
+use crate::ray::Ray;
+use crate::texture::{ImageTexture, Texture};
+
+/// Step in surface coordinates used to differentiate height maps
+const BUMP_STEP: f64 = 1e-3;
+
+/// How the shading normal is changed
+pub enum NormalPerturbation {
+    /// A tangent space normal map: red, green and blue are the coordinates of the normal along the derivative of the
+    /// point with respect to u, the one with respect to v and the normal, mapped from -1..1 to 0..1
+    NormalMap(Arc<dyn Texture>),
+    /// A height map (only its luminance is used) that displaces the surface along the normal by scale times its
+    /// value, without moving it. Solid textures like PerlinNoiseTexture work too
+    BumpMap {
+        height: Arc<dyn Texture>,
+        scale: f64,
+    },
+}
+
+/// Adds detail to a material by changing the normal it sees, without changing the geometry. Normal maps need surfaces
+/// with coordinates, like Sphere, Parallelogram or Triangle. The silhouettes stay smooth and the normal only bends the
+/// light, so very strong maps can make parts of the surface look black
+pub struct NormalMapped {
+    pub material: Arc<dyn Material>,
+    pub perturbation: NormalPerturbation,
+}
+
+impl NormalMapped {
+    pub fn normal_map(material: Arc<dyn Material>, texture: Arc<dyn Texture>) -> Arc<NormalMapped> {
+        Arc::new(NormalMapped {
+            material,
+            perturbation: NormalPerturbation::NormalMap(texture),
+        })
+    }
+
+    /// Load the normal map from an image. Its values are directions, so they are read as linear and not decoded
+    pub fn normal_map_from_file(material: Arc<dyn Material>, path: &str) -> Arc<NormalMapped> {
+        NormalMapped::normal_map(material, ImageTexture::data_or_fallback(path))
+    }
+
+    pub fn bump_map(
+        material: Arc<dyn Material>,
+        height: Arc<dyn Texture>,
+        scale: f64,
+    ) -> Arc<NormalMapped> {
+        Arc::new(NormalMapped {
+            material,
+            perturbation: NormalPerturbation::BumpMap { height, scale },
+        })
+    }
+
+    /// The outward facing normal after the perturbation, or None if the surface has no tangent frame
+    fn perturbed_normal(&self, record: &HitRecord) -> Option<Vector3> {
+        let normal: Vector3 = record.normal;
+
+        let perturbed: Vector3 = match &self.perturbation {
+            NormalPerturbation::NormalMap(texture) => {
+                let (tangent, bitangent): (Vector3, Vector3) =
+                    tangent_frame(&normal, &record.dp_du, &record.dp_dv)?;
+
+                let value: Color = texture.value_at(record);
+                (2.0 * value.x - 1.0) * tangent
+                    + (2.0 * value.y - 1.0) * bitangent
+                    + (2.0 * value.z - 1.0) * normal
+            }
+            NormalPerturbation::BumpMap { height, scale } => {
+                // Surfaces without coordinates get an arbitrary frame, which is enough for solid textures
+                let (dp_du, dp_dv): (Vector3, Vector3) = if record.dp_du.is_near_zero() {
+                    orthonormal_basis(&normal)
+                } else {
+                    (record.dp_du, record.dp_dv)
+                };
+                // Filtered like the other textures, so that bumps far away are smoothed out instead of aliasing
+                let height_at = |u: f64, v: f64, offset: Vector3| -> f64 {
+                    scale
+                        * height
+                            .filtered_value(
+                                SurfaceCoordinate { u, v },
+                                &(record.p + offset),
+                                record.footprint,
+                            )
+                            .luminance()
+                };
+                let (u, v): (f64, f64) = (record.surface_coords.u, record.surface_coords.v);
+                let center: f64 = height_at(u, v, Vector3::default());
+                let du: f64 = (height_at(u + BUMP_STEP, v, BUMP_STEP * dp_du) - center) / BUMP_STEP;
+                let dv: f64 = (height_at(u, v + BUMP_STEP, BUMP_STEP * dp_dv) - center) / BUMP_STEP;
+
+                // Derivatives of the displaced surface. The terms with the derivatives of the normal are left out,
+                // as they are small for small displacements
+                let perturbed: Vector3 = cross(&(dp_du + du * normal), &(dp_dv + dv * normal));
+
+                // The cross product points to the side given by the orientation of the coordinates
+                if dot(&cross(&dp_du, &dp_dv), &normal) < 0.0 {
+                    -perturbed
+                } else {
+                    perturbed
+                }
+            }
+        };
+
+        if perturbed.is_near_zero() {
+            return None;
+        }
+        let perturbed: Vector3 = unit_vector(perturbed);
+
+        Some(if dot(&perturbed, &normal) < 0.0 {
+            -perturbed
+        } else {
+            perturbed
+        })
+    }
+
+    /// A copy of record with the perturbed normal. The geometric normal is kept if the view direction would end up
+    /// below the perturbed surface, since materials can not scatter light coming from behind
+    fn shading_record(&self, ray_in: &Ray, record: &HitRecord) -> HitRecord {
+        let mut shading: HitRecord = record.clone();
+
+        if let Some(normal) = self.perturbed_normal(record)
+            && dot(&normal, &ray_in.direction) < 0.0
+        {
+            shading.normal = normal;
+            // Keep the tangent frame orthonormal around the new normal, with the lengths of the derivatives so that
+            // a bump map on top of this one still gets its slopes right
+            if let Some((tangent, bitangent)) = tangent_frame(&normal, &record.dp_du, &record.dp_dv)
+            {
+                shading.dp_du = record.dp_du.length() * tangent;
+                shading.dp_dv = record.dp_dv.length() * bitangent;
+            }
+        }
+        shading
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        self.material
+            .scatter(ray_in, &self.shading_record(ray_in, record), sca_att)
+    }
+
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        self.material
+            .evaluate(ray_in, &self.shading_record(ray_in, record), direction)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        self.material
+            .scattering_pdf(ray_in, &self.shading_record(ray_in, record), direction)
+    }
+
+    fn emitted(&self, record: &HitRecord) -> Color {
+        self.material.emitted(record)
+    }
+
+    fn emitted_spectrum(&self, record: &HitRecord, wavelength: f64) -> f64 {
+        self.material.emitted_spectrum(record, wavelength)
+    }
+
+    fn opacity(&self, surface_coords: SurfaceCoordinate, p: &Point3) -> f64 {
+        self.material.opacity(surface_coords, p)
+    }
+}
+
+/// Unit tangent and bitangent around normal, from the derivatives of the point. The tangent is dp_du made
+/// perpendicular to the normal (Gram-Schmidt) and the bitangent is perpendicular to both, on the side of dp_dv, so
+/// the frame is orthonormal even if the coordinates are curved or skewed. None if there are no derivatives
+pub fn tangent_frame(
+    normal: &Vector3,
+    dp_du: &Vector3,
+    dp_dv: &Vector3,
+) -> Option<(Vector3, Vector3)> {
+    let tangent: Vector3 = *dp_du - dot(dp_du, normal) * *normal;
+    if tangent.is_near_zero() {
+        return None;
+    }
+    let tangent: Vector3 = unit_vector(tangent);
+
+    let bitangent: Vector3 = cross(normal, &tangent);
+    if dot(&bitangent, dp_dv) < 0.0 {
+        Some((tangent, -bitangent))
+    } else {
+        Some((tangent, bitangent))
+    }
+}
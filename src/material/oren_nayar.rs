@@ -0,0 +1,106 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::hittable::HitRecord;
+use crate::material::microfacet::LocalFrame;
+use crate::material::{Material, ScatteredRayAndAttenuation};
+use crate::point3::color::Color;
+use crate::point3::{Vector3, dot, random_unit_vector, unit_vector};
+use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
+
+/// A rough diffuse material, like clay, concrete or plaster. The surface is made of tiny Lambertian V-shaped grooves
+/// (Oren and Nayar 1994), which makes it look flatter than Lambertian and brighter when lit from behind the viewer.
+/// Uses the qualitative model, as in pbrt: https://pbr-book.org/3ed-2018/Reflection_Models/Microfacet_Models
+pub struct OrenNayar {
+    pub texture: Arc<dyn Texture>,
+    a: f64,
+    b: f64,
+}
+
+impl OrenNayar {
+    /// sigma is the standard deviation of the slope of the grooves, in degrees. Zero is the same as Lambertian,
+    /// and 20 to 40 looks like most rough materials
+    pub fn from_texture(texture: Arc<dyn Texture>, sigma: f64) -> Arc<OrenNayar> {
+        let sigma_squared: f64 = sigma.to_radians().powi(2);
+
+        Arc::new(OrenNayar {
+            texture,
+            a: 1.0 - sigma_squared / (2.0 * (sigma_squared + 0.33)),
+            b: 0.45 * sigma_squared / (sigma_squared + 0.09),
+        })
+    }
+
+    pub fn from_color(color: Color, sigma: f64) -> Arc<OrenNayar> {
+        OrenNayar::from_texture(SolidColor::new(color), sigma)
+    }
+
+    /// The scattering function divided by the one of a Lambertian surface with the same color.
+    /// wo and wi are in the local frame of the surface
+    fn roughness_factor(&self, wo: &Vector3, wi: &Vector3) -> f64 {
+        let sin_theta_o: f64 = (1.0 - wo.z * wo.z).max(0.0).sqrt();
+        let sin_theta_i: f64 = (1.0 - wi.z * wi.z).max(0.0).sqrt();
+
+        // Cosine of the difference of the azimuths, only when both are well defined
+        let cos_phi_difference: f64 = if sin_theta_o > 1e-4 && sin_theta_i > 1e-4 {
+            ((wo.x * wi.x + wo.y * wi.y) / (sin_theta_o * sin_theta_i)).max(0.0)
+        } else {
+            0.0
+        };
+
+        // alpha is the biggest of the two polar angles and beta the smallest
+        let (sin_alpha, tan_beta): (f64, f64) = if wi.z.abs() > wo.z.abs() {
+            (sin_theta_o, sin_theta_i / wi.z.abs())
+        } else {
+            (sin_theta_i, sin_theta_o / wo.z.abs())
+        };
+
+        self.a + self.b * cos_phi_difference * sin_alpha * tan_beta
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+
+        if wo.z <= 0.0 {
+            return false;
+        }
+
+        // Cosine distributed, like Lambertian
+        let direction: Vector3 = Vector3::new(0.0, 0.0, 1.0) + random_unit_vector();
+        if direction.is_near_zero() {
+            return false;
+        }
+        let wi: Vector3 = unit_vector(direction);
+
+        sca_att.scattered_ray = Ray::new(record.p, frame.to_world(&wi));
+        // The cosine and the 1/pi cancel out with the pdf
+        sca_att.attenuation = self.texture.value_at(record) * self.roughness_factor(&wo, &wi);
+        sca_att.pdf = wi.z / PI;
+
+        true
+    }
+
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+        let wi: Vector3 = frame.to_local(&unit_vector(*direction));
+
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::black();
+        }
+
+        self.texture.value_at(record) * (self.roughness_factor(&wo, &wi) * wi.z / PI)
+    }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        dot(&record.normal, &unit_vector(*direction)).max(0.0) / PI
+    }
+}
@@ -0,0 +1,391 @@
+// A single material that covers most real surfaces, with the parameters artists know from other tools.
+// Resources:
+// - Physically based shading at Disney, Burley (2012): https://media.disneyanimation.com/uploads/production/publication_asset/48/asset/s2012_pbs_disney_brdf_notes_v3.pdf
+// - Extending the Disney BRDF to a BSDF with integrated subsurface scattering, Burley (2015)
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::hittable::HitRecord;
+use crate::material::microfacet::{Ggx, LocalFrame, SMOOTH_ALPHA, fresnel_schlick};
+use crate::material::{Material, MediumBoundary, ScatteredRayAndAttenuation, medium_boundary};
+use crate::point3::color::Color;
+use crate::point3::{Vector3, dot, random_unit_vector, unit_vector};
+use crate::ray::{MediumEntry, MediumStack, Ray, next_medium_id};
+use crate::texture::{SolidColor, Texture};
+
+/// A principled (Disney style) uber material: a diffuse base, a specular layer, sheen for cloth, a clear coat and
+/// glass like transmission, all blended by the parameters. Every parameter is a texture so it can change over the
+/// surface. The scalar ones use the luminance of the texture and go from 0 to 1.
+/// Build one with from_color or from_texture and the with_ methods, or use struct update syntax to drive other
+/// parameters with textures, and wrap it in an Arc
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>,
+    /// 0 for dielectrics (plastic, wood...) and 1 for metals, which take their reflection color from base_color
+    pub metallic: Arc<dyn Texture>,
+    /// Perceptual roughness of the specular layer and the transmission
+    pub roughness: Arc<dyn Texture>,
+    /// Reflectance at normal incidence of dielectrics. 0.5 is 4%, the one of most materials
+    pub specular: Arc<dyn Texture>,
+    /// How much the specular reflection of dielectrics is tinted by base_color
+    pub specular_tint: Arc<dyn Texture>,
+    /// Extra reflection at grazing angles, for cloth
+    pub sheen: Arc<dyn Texture>,
+    /// How much the sheen is tinted by base_color
+    pub sheen_tint: Arc<dyn Texture>,
+    /// Strength of a second, white specular layer, like the varnish of a car
+    pub clearcoat: Arc<dyn Texture>,
+    /// 0 gives a satin clear coat and 1 a glossy one
+    pub clearcoat_gloss: Arc<dyn Texture>,
+    /// 0 for opaque materials and 1 for glass. Transmitted light is tinted by base_color
+    pub transmission: Arc<dyn Texture>,
+    /// Refractive index used by the transmission
+    pub ior: f64,
+    /// Identifies the medium that transmitted rays go into, see Dielectric
+    pub medium_id: usize,
+}
+
+/// The parameters at a point of the surface
+struct Parameters {
+    base_color: Color,
+    metallic: f64,
+    roughness: f64,
+    specular_f0: Color,
+    sheen: Color,
+    clearcoat: f64,
+    transmission: f64,
+    specular_ggx: Ggx,
+    clearcoat_ggx: Ggx,
+    /// Probabilities of sampling the diffuse, specular, clear coat and transmission lobes
+    lobe_probabilities: [f64; 4],
+}
+
+fn constant(value: f64) -> Arc<dyn Texture> {
+    SolidColor::new(Color::new(value, value, value))
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn lerp_color(a: Color, b: Color, t: f64) -> Color {
+    a + t * (b - a)
+}
+
+impl Principled {
+    /// A rough white plastic like material, tinted by color
+    pub fn from_color(color: Color) -> Principled {
+        Principled::from_texture(SolidColor::new(color))
+    }
+
+    pub fn from_texture(texture: Arc<dyn Texture>) -> Principled {
+        Principled {
+            base_color: texture,
+            metallic: constant(0.0),
+            roughness: constant(0.5),
+            specular: constant(0.5),
+            specular_tint: constant(0.0),
+            sheen: constant(0.0),
+            sheen_tint: constant(0.5),
+            clearcoat: constant(0.0),
+            clearcoat_gloss: constant(1.0),
+            transmission: constant(0.0),
+            ior: 1.5,
+            medium_id: next_medium_id(),
+        }
+    }
+
+    pub fn with_metallic(mut self, metallic: f64) -> Principled {
+        self.metallic = constant(metallic);
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: f64) -> Principled {
+        self.roughness = constant(roughness);
+        self
+    }
+
+    pub fn with_specular(mut self, specular: f64, specular_tint: f64) -> Principled {
+        self.specular = constant(specular);
+        self.specular_tint = constant(specular_tint);
+        self
+    }
+
+    pub fn with_sheen(mut self, sheen: f64, sheen_tint: f64) -> Principled {
+        self.sheen = constant(sheen);
+        self.sheen_tint = constant(sheen_tint);
+        self
+    }
+
+    pub fn with_clearcoat(mut self, clearcoat: f64, clearcoat_gloss: f64) -> Principled {
+        self.clearcoat = constant(clearcoat);
+        self.clearcoat_gloss = constant(clearcoat_gloss);
+        self
+    }
+
+    pub fn with_transmission(mut self, transmission: f64, ior: f64) -> Principled {
+        self.transmission = constant(transmission);
+        self.ior = ior;
+        self
+    }
+
+    fn parameters(&self, record: &HitRecord) -> Parameters {
+        let scalar = |texture: &Arc<dyn Texture>| -> f64 {
+            texture.value_at(record).luminance().clamp(0.0, 1.0)
+        };
+
+        let base_color: Color = self.base_color.value_at(record);
+        let metallic: f64 = scalar(&self.metallic);
+        let roughness: f64 = scalar(&self.roughness);
+        let transmission: f64 = scalar(&self.transmission);
+        let clearcoat: f64 = scalar(&self.clearcoat);
+
+        // The hue of the base color, without its brightness
+        let luminance: f64 = base_color.luminance();
+        let tint: Color = if luminance > 0.0 {
+            base_color / luminance
+        } else {
+            Color::white()
+        };
+
+        let dielectric_f0: Color = 0.08
+            * scalar(&self.specular)
+            * lerp_color(Color::white(), tint, scalar(&self.specular_tint));
+        let sheen: Color =
+            scalar(&self.sheen) * lerp_color(Color::white(), tint, scalar(&self.sheen_tint));
+
+        // Perfectly smooth surfaces are kept slightly rough, so that all the lobes can be evaluated and mixed
+        let specular_ggx: Ggx = Ggx {
+            alpha: Ggx::from_roughness(roughness).alpha.max(SMOOTH_ALPHA),
+        };
+        let clearcoat_ggx: Ggx = Ggx {
+            alpha: lerp(0.1, 0.001, scalar(&self.clearcoat_gloss)).max(SMOOTH_ALPHA),
+        };
+
+        // Lobes are chosen in proportion to their weight in the mix
+        let weights: [f64; 4] = [
+            (1.0 - metallic) * (1.0 - transmission),
+            1.0 - (1.0 - metallic) * transmission,
+            0.25 * clearcoat,
+            (1.0 - metallic) * transmission,
+        ];
+        let total: f64 = weights.iter().sum();
+
+        Parameters {
+            base_color,
+            metallic,
+            roughness,
+            specular_f0: lerp_color(dielectric_f0, base_color, metallic),
+            sheen,
+            clearcoat,
+            transmission,
+            specular_ggx,
+            clearcoat_ggx,
+            lobe_probabilities: weights.map(|weight| weight / total),
+        }
+    }
+
+    /// What a ray transmitted inside remembers about the material
+    pub fn medium_entry(&self) -> MediumEntry {
+        MediumEntry {
+            id: self.medium_id,
+            priority: 0,
+            refraction_index: self.ior,
+            absorption: Color::black(),
+        }
+    }
+
+    /// Refractive index of the side the ray goes into over the one it comes from, and the media of the ray if it is
+    /// transmitted. The surface is always there, even inside media that win over it, as it can be opaque
+    fn transmission_boundary(&self, ray_in: &Ray, record: &HitRecord) -> (f64, MediumStack) {
+        let boundary: MediumBoundary = medium_boundary(self.medium_entry(), ray_in, record);
+
+        (
+            boundary.index_after / boundary.index_before,
+            boundary.media_after,
+        )
+    }
+
+    /// The scattering function times the cosine of wi, in the local frame of the surface
+    fn evaluate_local(
+        &self,
+        parameters: &Parameters,
+        wo: &Vector3,
+        wi: &Vector3,
+        front_face: bool,
+        eta: f64,
+    ) -> Color {
+        if wo.z <= 0.0 {
+            return Color::black();
+        }
+
+        // From inside, only the boundary of the transmission is seen. The color was applied when entering
+        if !front_face {
+            return Color::white() * parameters.specular_ggx.evaluate_dielectric(wo, wi, eta);
+        }
+
+        let dielectric_weight: f64 = (1.0 - parameters.metallic) * parameters.transmission;
+        let mut result: Color = Color::black();
+
+        if dielectric_weight > 0.0 {
+            let value: f64 = parameters.specular_ggx.evaluate_dielectric(wo, wi, eta);
+            // Only the transmitted light takes the color of the material
+            result = if wi.z < 0.0 {
+                dielectric_weight * value * parameters.base_color
+            } else {
+                Color::white() * (dielectric_weight * value)
+            };
+        }
+
+        if wi.z <= 0.0 {
+            return result;
+        }
+
+        let h: Vector3 = unit_vector(*wo + *wi);
+        let wi_dot_h: f64 = dot(wi, &h);
+
+        // Diffuse with retro-reflection at grazing angles for rough surfaces
+        let diffuse_weight: f64 = (1.0 - parameters.metallic) * (1.0 - parameters.transmission);
+        if diffuse_weight > 0.0 {
+            let fresnel_90: f64 = 0.5 + 2.0 * parameters.roughness * wi_dot_h * wi_dot_h;
+            let schlick =
+                |cos_theta: f64| -> f64 { 1.0 + (fresnel_90 - 1.0) * (1.0 - cos_theta).powi(5) };
+            let diffuse: Color = parameters.base_color * (schlick(wo.z) * schlick(wi.z) / PI);
+            let sheen: Color = parameters.sheen * (1.0 - wi_dot_h).powi(5);
+
+            result = result + diffuse_weight * wi.z * (diffuse + sheen);
+        }
+
+        let specular_weight: f64 = 1.0 - dielectric_weight;
+        let specular: Color = fresnel_schlick(wi_dot_h, &parameters.specular_f0)
+            * (parameters.specular_ggx.distribution(&h)
+                * parameters.specular_ggx.masking_shadowing(wo, wi)
+                / (4.0 * wo.z));
+        result = result + specular_weight * specular;
+
+        if parameters.clearcoat > 0.0 {
+            let clearcoat: Color = fresnel_schlick(wi_dot_h, &Color::new(0.04, 0.04, 0.04))
+                * (parameters.clearcoat_ggx.distribution(&h)
+                    * parameters.clearcoat_ggx.masking_shadowing(wo, wi)
+                    / (4.0 * wo.z));
+            result = result + 0.25 * parameters.clearcoat * clearcoat;
+        }
+
+        result
+    }
+
+    /// Probability density of sampling wi, in the local frame of the surface
+    fn pdf_local(
+        &self,
+        parameters: &Parameters,
+        wo: &Vector3,
+        wi: &Vector3,
+        front_face: bool,
+        eta: f64,
+    ) -> f64 {
+        if !front_face {
+            return parameters.specular_ggx.dielectric_pdf(wo, wi, eta);
+        }
+
+        let [diffuse, specular, clearcoat, transmission] = parameters.lobe_probabilities;
+        let mut pdf: f64 = transmission * parameters.specular_ggx.dielectric_pdf(wo, wi, eta);
+
+        if wi.z <= 0.0 {
+            return pdf;
+        }
+
+        pdf += diffuse * wi.z / PI;
+        pdf += specular * parameters.specular_ggx.reflection_pdf(wo, wi);
+        pdf += clearcoat * parameters.clearcoat_ggx.reflection_pdf(wo, wi);
+        pdf
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        let parameters: Parameters = self.parameters(record);
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+        let (eta, media_after): (f64, MediumStack) = self.transmission_boundary(ray_in, record);
+
+        if wo.z <= 0.0 {
+            return false;
+        }
+
+        // Choose one lobe to sample. The result is weighted with the pdf of all of them, so any choice is unbiased
+        let [diffuse, specular, clearcoat, _] = parameters.lobe_probabilities;
+        let choice: f64 = rand::random_range(0.0..1.0);
+        let sampled: Option<Vector3> = if !record.front_face {
+            parameters.specular_ggx.sample_dielectric(&wo, eta)
+        } else if choice < diffuse {
+            let direction: Vector3 = Vector3::new(0.0, 0.0, 1.0) + random_unit_vector();
+            if direction.is_near_zero() {
+                None
+            } else {
+                Some(unit_vector(direction))
+            }
+        } else if choice < diffuse + specular {
+            parameters.specular_ggx.sample_reflection(&wo)
+        } else if choice < diffuse + specular + clearcoat {
+            parameters.clearcoat_ggx.sample_reflection(&wo)
+        } else {
+            parameters.specular_ggx.sample_dielectric(&wo, eta)
+        };
+
+        let wi: Vector3 = match sampled {
+            Some(wi) => wi,
+            None => return false,
+        };
+
+        let pdf: f64 = self.pdf_local(&parameters, &wo, &wi, record.front_face, eta);
+        if pdf <= 0.0 {
+            return false;
+        }
+
+        // Transmitted rays go into the media on the other side, reflected ones stay in the same
+        let media: Option<Arc<MediumStack>> = if wi.z < 0.0 {
+            Some(Arc::new(media_after))
+        } else {
+            ray_in.media.clone()
+        };
+        sca_att.scattered_ray = Ray::new(record.p, frame.to_world(&wi)).with_media(media);
+        sca_att.attenuation =
+            self.evaluate_local(&parameters, &wo, &wi, record.front_face, eta) / pdf;
+        sca_att.pdf = pdf;
+
+        true
+    }
+
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+        let wi: Vector3 = frame.to_local(&unit_vector(*direction));
+
+        self.evaluate_local(
+            &self.parameters(record),
+            &wo,
+            &wi,
+            record.front_face,
+            self.transmission_boundary(ray_in, record).0,
+        )
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+        let wi: Vector3 = frame.to_local(&unit_vector(*direction));
+
+        self.pdf_local(
+            &self.parameters(record),
+            &wo,
+            &wi,
+            record.front_face,
+            self.transmission_boundary(ray_in, record).0,
+        )
+    }
+}
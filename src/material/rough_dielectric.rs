@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use crate::hittable::HitRecord;
+use crate::material::microfacet::{Ggx, LocalFrame};
+use crate::material::{
+    Material, MediumBoundary, ScatteredRayAndAttenuation, absorption_from_color, medium_boundary,
+    pass_through,
+};
+use crate::point3::color::Color;
+use crate::point3::{Vector3, unit_vector};
+use crate::ray::{MediumEntry, MediumStack, Ray, next_medium_id};
+use crate::texture::{SolidColor, Texture};
+
+/// A dielectric with a rough surface, like frosted or ground glass: light is reflected or refracted by GGX microfacets
+/// (Walter et al. 2007) with the exact Fresnel equations. With zero roughness it behaves like Dielectric, and like it,
+/// rays keep track of being inside, so it can be nested in other media (a frosted ice cube in water)
+pub struct RoughDielectric {
+    /// Refractive index in vacuum or air. Inside other media, their index is used on the other side
+    pub refraction_index: f64,
+    /// Perceptual roughness, from 0 (polished) to 1. Only the luminance of the texture is used
+    pub roughness: Arc<dyn Texture>,
+    /// Fraction of the light absorbed per unit of distance travelled inside, for each color channel
+    pub absorption: Color,
+    /// Identifies the medium in the media of rays, see Dielectric
+    pub medium_id: usize,
+}
+
+impl RoughDielectric {
+    pub fn new(refraction_index: f64, roughness: f64) -> Arc<RoughDielectric> {
+        RoughDielectric::with_roughness_texture(
+            refraction_index,
+            SolidColor::new(Color::new(roughness, roughness, roughness)),
+        )
+    }
+
+    /// A dielectric whose roughness changes over the surface, for example glass that is only frosted in some places
+    pub fn with_roughness_texture(
+        refraction_index: f64,
+        roughness: Arc<dyn Texture>,
+    ) -> Arc<RoughDielectric> {
+        Arc::new(RoughDielectric {
+            refraction_index,
+            roughness,
+            absorption: Color::black(),
+            medium_id: next_medium_id(),
+        })
+    }
+
+    /// Tinted frosted glass: light that travels distance inside it gets multiplied by color
+    pub fn tinted(
+        refraction_index: f64,
+        roughness: f64,
+        color: Color,
+        distance: f64,
+    ) -> Arc<RoughDielectric> {
+        Arc::new(RoughDielectric {
+            refraction_index,
+            roughness: SolidColor::new(Color::new(roughness, roughness, roughness)),
+            absorption: absorption_from_color(color, distance),
+            medium_id: next_medium_id(),
+        })
+    }
+
+    fn distribution(&self, record: &HitRecord) -> Ggx {
+        Ggx::from_roughness(self.roughness.value_at(record).luminance())
+    }
+
+    /// What a ray going inside remembers about the material
+    pub fn medium_entry(&self) -> MediumEntry {
+        MediumEntry {
+            id: self.medium_id,
+            priority: 0,
+            refraction_index: self.refraction_index,
+            absorption: self.absorption,
+        }
+    }
+
+    fn boundary(&self, ray_in: &Ray, record: &HitRecord) -> MediumBoundary {
+        medium_boundary(self.medium_entry(), ray_in, record)
+    }
+}
+
+/// Refractive index of the side the ray goes into over the one it comes from
+fn eta(boundary: &MediumBoundary) -> f64 {
+    boundary.index_after / boundary.index_before
+}
+
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        let ggx: Ggx = self.distribution(record);
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+
+        if wo.z <= 0.0 {
+            return false;
+        }
+
+        let boundary: MediumBoundary = self.boundary(ray_in, record);
+        if boundary.ignored {
+            return pass_through(
+                ray_in,
+                record,
+                sca_att,
+                boundary.media_after,
+                boundary.transmittance,
+            );
+        }
+
+        // The Fresnel equations choose between reflection and refraction, so they cancel out of the attenuation
+        let wi: Vector3 = match ggx.sample_dielectric(&wo, eta(&boundary)) {
+            Some(wi) => wi,
+            None => return false,
+        };
+
+        // Refracted rays go into the media on the other side, reflected ones stay in the same
+        let media: Option<Arc<MediumStack>> = if wi.z < 0.0 {
+            Some(Arc::new(boundary.media_after))
+        } else {
+            ray_in.media.clone()
+        };
+        sca_att.scattered_ray = Ray::new(record.p, frame.to_world(&wi)).with_media(media);
+        let transmittance: Color = boundary.transmittance;
+
+        if ggx.is_smooth() {
+            sca_att.attenuation = transmittance;
+            sca_att.pdf = 0.0;
+            return true;
+        }
+
+        sca_att.attenuation = transmittance * (ggx.masking_shadowing(&wo, &wi) / ggx.masking(&wo));
+        sca_att.pdf = self.scattering_pdf(ray_in, record, &sca_att.scattered_ray.direction);
+
+        true
+    }
+
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+        let wi: Vector3 = frame.to_local(&unit_vector(*direction));
+
+        let boundary: MediumBoundary = self.boundary(ray_in, record);
+        if boundary.ignored {
+            return Color::black();
+        }
+
+        boundary.transmittance
+            * self
+                .distribution(record)
+                .evaluate_dielectric(&wo, &wi, eta(&boundary))
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let frame: LocalFrame = LocalFrame::new(&record.normal);
+        let wo: Vector3 = frame.to_local(&(-unit_vector(ray_in.direction)));
+        let wi: Vector3 = frame.to_local(&unit_vector(*direction));
+
+        let boundary: MediumBoundary = self.boundary(ray_in, record);
+        if boundary.ignored {
+            return 0.0;
+        }
+
+        self.distribution(record)
+            .dielectric_pdf(&wo, &wi, eta(&boundary))
+    }
+}
@@ -0,0 +1,204 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::hittable::HitRecord;
+use crate::material::microfacet::fresnel_dielectric;
+use crate::material::{Material, MediumBoundary, ScatteredRayAndAttenuation, medium_boundary};
+use crate::point3::color::Color;
+use crate::point3::spectrum::{rgb_to_spectrum, sample_wavelength, wavelength_weight};
+use crate::point3::{Vector3, dot, random_unit_vector, reflect, refract, unit_vector};
+use crate::ray::{MediumEntry, Ray, next_medium_id};
+
+/// A translucent material, like skin, wax, marble or milk, where light goes inside and bounces around before coming
+/// back out some distance away. The inside is a homogeneous medium: rays entering through the smooth dielectric
+/// boundary follow a random walk, and each step is a bounce of the path, so the maximum depth of the camera needs to
+/// be big enough for the walk to get back out. The object must be closed, like the models loaded with load_model.
+/// Like dispersion, the walk follows a single wavelength, as each one travels a different distance between bounces.
+/// Light that reaches the boundary from inside always leaves, in a cosine distributed direction (as in Blender's
+/// Cycles), so that lights can be sampled at the exit points. Rays keep track of being inside, like with Dielectric,
+/// so the boundary uses the refractive index of the medium around the object
+pub struct Subsurface {
+    /// Scattering coefficient, per unit of distance and for each color channel
+    pub scattering: Color,
+    /// Absorption coefficient, per unit of distance and for each color channel
+    pub absorption: Color,
+    pub refraction_index: f64,
+    /// Identifies the medium in the media of rays, see Dielectric
+    pub medium_id: usize,
+}
+
+impl Subsurface {
+    pub fn new(scattering: Color, absorption: Color, refraction_index: f64) -> Arc<Subsurface> {
+        Arc::new(Subsurface {
+            scattering,
+            absorption,
+            refraction_index,
+            medium_id: next_medium_id(),
+        })
+    }
+
+    /// What a ray going inside remembers about the material. The random walk takes care of the absorption, so
+    /// there is none here
+    pub fn medium_entry(&self) -> MediumEntry {
+        MediumEntry {
+            id: self.medium_id,
+            priority: 0,
+            refraction_index: self.refraction_index,
+            absorption: Color::black(),
+        }
+    }
+
+    /// An object that looks like color when it is thick, where each channel travels on average mean_free_path
+    /// between bounces. Bigger mean free paths give more translucent objects, and a redder one gives the glow of skin
+    pub fn from_color(
+        color: Color,
+        mean_free_path: Color,
+        refraction_index: f64,
+    ) -> Arc<Subsurface> {
+        // Albedo of a single bounce that gives color after many of them (Chiang et al. 2016,
+        // "Practical and Controllable Subsurface Scattering for Production Path Tracing")
+        let single_albedo = |a: f64| -> f64 {
+            let a: f64 = a.clamp(0.0, 0.999);
+            let s: f64 = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+            1.0 - s * s
+        };
+        let channel = |c: f64, distance: f64| -> (f64, f64) {
+            let extinction: f64 = 1.0 / distance.max(1e-6);
+            let albedo: f64 = single_albedo(c);
+            (albedo * extinction, (1.0 - albedo) * extinction)
+        };
+
+        let (red, green, blue) = (
+            channel(color.x, mean_free_path.x),
+            channel(color.y, mean_free_path.y),
+            channel(color.z, mean_free_path.z),
+        );
+
+        Subsurface::new(
+            Color::new(red.0, green.0, blue.0),
+            Color::new(red.1, green.1, blue.1),
+            refraction_index,
+        )
+    }
+
+    /// Scattering and extinction coefficients at wavelength (in nanometers)
+    fn coefficients(&self, wavelength: f64) -> (f64, f64) {
+        let scattering: f64 = rgb_to_spectrum(&self.scattering, wavelength);
+        let absorption: f64 = rgb_to_spectrum(&self.absorption, wavelength);
+
+        (scattering, scattering + absorption)
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sca_att: &mut ScatteredRayAndAttenuation,
+    ) -> bool {
+        let unit_direction: Vector3 = unit_vector(ray_in.direction);
+        let boundary: MediumBoundary = medium_boundary(self.medium_entry(), ray_in, record);
+
+        // From outside: reflect or go in through the boundary
+        if record.front_face {
+            let cos_theta: f64 = dot(&record.normal, &(-unit_direction)).min(1.0);
+            let eta: f64 = boundary.index_after / boundary.index_before;
+            if rand::random_range(0.0..1.0) < fresnel_dielectric(cos_theta, eta) {
+                sca_att.scattered_ray = Ray::new(record.p, reflect(unit_direction, record.normal));
+                sca_att.attenuation = Color::white();
+                sca_att.pdf = 0.0;
+                return true;
+            }
+
+            let direction: Vector3 = refract(unit_direction, record.normal, 1.0 / eta);
+            let (wavelength, wavelength_color): (f64, Color) = path_wavelength(ray_in);
+            sca_att.scattered_ray = Ray::new(record.p, direction)
+                .with_wavelength(Some(wavelength))
+                .with_media(Some(Arc::new(boundary.media_after)));
+            sca_att.attenuation = wavelength_color;
+            sca_att.pdf = 0.0;
+            return true;
+        }
+
+        // A path that got inside without going through the boundary, like one that started inside, picks its
+        // wavelength here and tries again
+        let wavelength: f64 = match ray_in.wavelength {
+            Some(wavelength) => wavelength,
+            None => {
+                let (wavelength, wavelength_color): (f64, Color) = path_wavelength(ray_in);
+                sca_att.scattered_ray =
+                    Ray::new(ray_in.origin, ray_in.direction).with_wavelength(Some(wavelength));
+                sca_att.attenuation = wavelength_color;
+                sca_att.pdf = 0.0;
+                return true;
+            }
+        };
+
+        // The ray went from its origin to the boundary, unless it bounced somewhere before
+        let (scattering, extinction): (f64, f64) = self.coefficients(wavelength);
+        if extinction > 0.0 {
+            let random: f64 = rand::random_range(0.0..1.0);
+            let distance: f64 = -(1.0 - random).ln() / extinction;
+            let ray_length: f64 = ray_in.direction.length();
+
+            if distance < record.t * ray_length {
+                // Isotropic bounce. The transmittance cancels out with the probability of the distance
+                let albedo: f64 = scattering / extinction;
+                sca_att.scattered_ray =
+                    Ray::new(ray_in.at(distance / ray_length), random_unit_vector())
+                        .with_wavelength(Some(wavelength));
+                sca_att.attenuation = Color::new(albedo, albedo, albedo);
+                sca_att.pdf = 0.0;
+                return true;
+            }
+        }
+
+        // Leave the object. The normal points inside, towards the ray
+        let direction: Vector3 = {
+            let temp: Vector3 = -record.normal + random_unit_vector();
+            if temp.is_near_zero() {
+                -record.normal
+            } else {
+                temp
+            }
+        };
+
+        sca_att.scattered_ray = Ray::new(record.p, direction)
+            .with_wavelength(Some(wavelength))
+            .with_media(Some(Arc::new(boundary.media_after)));
+        // The cosine and the 1/pi cancel out with the pdf, and the transmittance with the probability of getting here
+        sca_att.attenuation = Color::white();
+        sca_att.pdf = self.scattering_pdf(ray_in, record, &direction);
+
+        sca_att.pdf > 0.0
+    }
+
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        if record.front_face {
+            return Color::black();
+        }
+
+        Color::white() * self.scattering_pdf(ray_in, record, direction)
+    }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        if record.front_face {
+            return 0.0;
+        }
+
+        dot(&(-record.normal), &unit_vector(*direction)).max(0.0) / PI
+    }
+}
+
+/// The wavelength of the path and its weight. White light entering picks one, like in a dispersive material, so
+/// that each walk only has to follow a single set of coefficients
+fn path_wavelength(ray_in: &Ray) -> (f64, Color) {
+    match ray_in.wavelength {
+        Some(wavelength) => (wavelength, Color::white()),
+        None => {
+            let wavelength: f64 = sample_wavelength();
+            (wavelength, wavelength_weight(wavelength))
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::array::from_fn;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::perlin::Fbm;
+use crate::point3::{Point3, Vector3};
+
+// Noise functions other than Perlin's, which has its own module
+
+/// Skew and unskew factors between the cubic grid and the grid of tetrahedra in 3D
+const F3: f64 = 1.0 / 3.0;
+const G3: f64 = 1.0 / 6.0;
+
+/// Directions to the middle of the edges of a cube
+const GRADIENTS: [[f64; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+/// Ken Perlin's simplex noise, following Stefan Gustavson's "Simplex noise demystified". Space is split into
+/// tetrahedra instead of cubes, so each point only adds four corners instead of eight, and the noise has no
+/// visible grid. Values go from -1 to 1 and are 0 at the corners
+pub struct SimplexNoise {
+    /// A permutation of 0..256, twice, so indices can be added without wrapping
+    permutation: [usize; 512],
+}
+
+impl SimplexNoise {
+    /// Noise from a seed. The same seed always gives the same noise
+    pub fn new(seed: u64) -> SimplexNoise {
+        let mut rng: SmallRng = SmallRng::seed_from_u64(seed);
+        let mut table: [usize; 256] = from_fn(|i| i);
+        for i in (1..table.len()).rev() {
+            let j: usize = rng.random_range(0..=i);
+            table.swap(i, j);
+        }
+
+        SimplexNoise {
+            permutation: from_fn(|i| table[i & 255]),
+        }
+    }
+
+    pub fn noise(&self, p: &Point3) -> f64 {
+        // Cell of the skewed grid, and the position relative to its first corner in normal space
+        let skew: f64 = (p.x + p.y + p.z) * F3;
+        let i: f64 = (p.x + skew).floor();
+        let j: f64 = (p.y + skew).floor();
+        let k: f64 = (p.z + skew).floor();
+        let unskew: f64 = (i + j + k) * G3;
+        let first: Vector3 =
+            Vector3::new(p.x - (i - unskew), p.y - (j - unskew), p.z - (k - unskew));
+
+        // The tetrahedron of the cell that has the point, from the order of its coordinates
+        let (second_corner, third_corner): ([usize; 3], [usize; 3]) = if first.x >= first.y {
+            if first.y >= first.z {
+                ([1, 0, 0], [1, 1, 0])
+            } else if first.x >= first.z {
+                ([1, 0, 0], [1, 0, 1])
+            } else {
+                ([0, 0, 1], [1, 0, 1])
+            }
+        } else if first.y < first.z {
+            ([0, 0, 1], [0, 1, 1])
+        } else if first.x < first.z {
+            ([0, 1, 0], [0, 1, 1])
+        } else {
+            ([0, 1, 0], [1, 1, 0])
+        };
+        let corners: [[usize; 3]; 4] = [[0, 0, 0], second_corner, third_corner, [1, 1, 1]];
+
+        let (ii, jj, kk): (usize, usize, usize) = (
+            (i as i64 & 255) as usize,
+            (j as i64 & 255) as usize,
+            (k as i64 & 255) as usize,
+        );
+
+        let mut accum: f64 = 0.0;
+        for (n, corner) in corners.iter().enumerate() {
+            let offset: Vector3 = Vector3::new(
+                first.x - corner[0] as f64 + n as f64 * G3,
+                first.y - corner[1] as f64 + n as f64 * G3,
+                first.z - corner[2] as f64 + n as f64 * G3,
+            );
+            let falloff: f64 = 0.6 - offset.dot(offset);
+            if falloff <= 0.0 {
+                continue;
+            }
+
+            let gradient_index: usize = self.permutation[ii
+                + corner[0]
+                + self.permutation[jj + corner[1] + self.permutation[kk + corner[2]]]]
+                % 12;
+            let gradient: [f64; 3] = GRADIENTS[gradient_index];
+            let falloff_squared: f64 = falloff * falloff;
+
+            accum += falloff_squared
+                * falloff_squared
+                * (gradient[0] * offset.x + gradient[1] * offset.y + gradient[2] * offset.z);
+        }
+
+        // Scale to -1..1
+        32.0 * accum
+    }
+
+    pub fn fbm(&self, p: &Point3, fbm: &Fbm) -> f64 {
+        fbm.sum(p, |q| self.noise(q))
+    }
+}
+
+/// Which distance to the feature points Worley noise gives
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WorleyDistance {
+    /// To the closest point: round cells, bright at their borders
+    Closest,
+    /// To the second closest point
+    SecondClosest,
+    /// Second closest minus closest: zero on the borders between cells, like cracks or scales
+    Border,
+}
+
+/// Steven Worley's cellular noise: every cell of a grid has a random point, and the noise is the distance to the
+/// closest ones. Gives cells like the ones of stone, scales, foam or giraffe skin
+pub struct WorleyNoise {
+    seed: u64,
+}
+
+impl WorleyNoise {
+    pub fn new(seed: u64) -> WorleyNoise {
+        WorleyNoise { seed }
+    }
+
+    /// The feature point of the cell with corner (i, j, k)
+    fn feature_point(&self, i: i64, j: i64, k: i64) -> Point3 {
+        let mut state: u64 = self.seed
+            ^ (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (j as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+            ^ (k as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+        let mut next = || -> f64 {
+            // SplitMix64
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z: u64 = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+            (z >> 11) as f64 / (1_u64 << 53) as f64
+        };
+
+        Point3::new(i as f64 + next(), j as f64 + next(), k as f64 + next())
+    }
+
+    /// Distances from p to the closest and the second closest feature points
+    pub fn distances(&self, p: &Point3) -> (f64, f64) {
+        let (i, j, k): (i64, i64, i64) =
+            (p.x.floor() as i64, p.y.floor() as i64, p.z.floor() as i64);
+
+        let mut closest: f64 = f64::INFINITY;
+        let mut second_closest: f64 = f64::INFINITY;
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                for dk in -1..=1 {
+                    let distance: f64 = (self.feature_point(i + di, j + dj, k + dk) - *p).length();
+                    if distance < closest {
+                        second_closest = closest;
+                        closest = distance;
+                    } else if distance < second_closest {
+                        second_closest = distance;
+                    }
+                }
+            }
+        }
+
+        (closest, second_closest)
+    }
+
+    pub fn noise(&self, p: &Point3, distance: WorleyDistance) -> f64 {
+        let (closest, second_closest): (f64, f64) = self.distances(p);
+
+        match distance {
+            WorleyDistance::Closest => closest,
+            WorleyDistance::SecondClosest => second_closest,
+            WorleyDistance::Border => second_closest - closest,
+        }
+    }
+}
@@ -1,11 +1,15 @@
 use std::array::from_fn;
 
-use rand::random_range;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
 
-use crate::point3::{Point3, Vector3, random_vector};
+use crate::point3::{Point3, Vector3};
 
 const POINT_COUNT: u32 = 256;
 
+/// Seed of create_perlin_noise
+const DEFAULT_SEED: u64 = 0;
+
 // To do: deal with the large amount of conversion done by these functions
 
 pub struct PerlinNoise {
@@ -15,34 +19,94 @@ pub struct PerlinNoise {
     pub z_perm: [u32; POINT_COUNT as usize],
 }
 
+/// Perlin noise with the default seed, the same on every run
 pub fn create_perlin_noise() -> PerlinNoise {
-    let random_vectors: [Vector3; POINT_COUNT as usize] = from_fn(|_i| random_vector(-1.0, 1.0));
-
-    let x_perm: [u32; POINT_COUNT as usize] = perlin_generate_perm();
-    let y_perm: [u32; POINT_COUNT as usize] = perlin_generate_perm();
-    let z_perm: [u32; POINT_COUNT as usize] = perlin_generate_perm();
-
-    PerlinNoise {
-        random_vectors,
-        x_perm,
-        y_perm,
-        z_perm,
-    }
+    PerlinNoise::new(DEFAULT_SEED)
 }
 
-fn perlin_generate_perm() -> [u32; POINT_COUNT as usize] {
+fn perlin_generate_perm(rng: &mut SmallRng) -> [u32; POINT_COUNT as usize] {
     let mut perm: [u32; POINT_COUNT as usize] = from_fn(|i| i as u32);
-    // You have to reverse it, something like n..1 will be silently initialized as empty
-    // for i in (1..POINT_COUNT as usize).rev() { // Clippy will cach this error unless you do an "as" conversion, like Im doing in this commented line
-    for i in (POINT_COUNT as usize)..0 {
-        let j: usize = random_range(0..=i) as usize;
+    // Fisher-Yates shuffle. It has to go backwards, and n..1 is silently empty, so reverse 1..n instead
+    for i in (1..POINT_COUNT as usize).rev() {
+        let j: usize = rng.random_range(0..=i);
         perm.swap(i, j);
     }
 
     perm
 }
 
+/// Parameters of fractal Brownian motion: the sum of octaves of noise, each one at lacunarity times the frequency
+/// and gain times the amplitude of the one before
+#[derive(Clone, Copy, Debug)]
+pub struct Fbm {
+    pub octaves: u32,
+    pub lacunarity: f64,
+    pub gain: f64,
+}
+
+impl Fbm {
+    /// Each octave at double the frequency and half the amplitude, the usual choice
+    pub fn new(octaves: u32) -> Fbm {
+        Fbm {
+            octaves,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    pub fn with_lacunarity(mut self, lacunarity: f64) -> Fbm {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// Bigger gains give rougher noise, as the small details are stronger
+    pub fn with_gain(mut self, gain: f64) -> Fbm {
+        self.gain = gain;
+        self
+    }
+
+    /// Sum of the octaves of noise at p
+    pub fn sum(&self, p: &Point3, noise: impl Fn(&Point3) -> f64) -> f64 {
+        let mut accum: f64 = 0.0;
+        let mut temp_p: Point3 = *p;
+        let mut weight: f64 = 1.0;
+
+        for _i in 0..self.octaves {
+            accum += weight * noise(&temp_p);
+            weight *= self.gain;
+            // To do: implement mulassign for point3
+            temp_p = self.lacunarity * temp_p;
+        }
+
+        accum
+    }
+}
+
 impl PerlinNoise {
+    /// Noise from a seed. The same seed always gives the same noise
+    pub fn new(seed: u64) -> PerlinNoise {
+        let mut rng: SmallRng = SmallRng::seed_from_u64(seed);
+
+        let random_vectors: [Vector3; POINT_COUNT as usize] = from_fn(|_i| {
+            Vector3::new(
+                rng.random_range(-1.0..1.0),
+                rng.random_range(-1.0..1.0),
+                rng.random_range(-1.0..1.0),
+            )
+        });
+
+        let x_perm: [u32; POINT_COUNT as usize] = perlin_generate_perm(&mut rng);
+        let y_perm: [u32; POINT_COUNT as usize] = perlin_generate_perm(&mut rng);
+        let z_perm: [u32; POINT_COUNT as usize] = perlin_generate_perm(&mut rng);
+
+        PerlinNoise {
+            random_vectors,
+            x_perm,
+            y_perm,
+            z_perm,
+        }
+    }
+
     pub fn noise(&self, p: &Point3) -> f64 {
         // Not the fractional part, since for 3.6 -> 0.6 but for -3.6 -> -3.6 - (-4) = 0.4
         let u: f64 = p.x - p.x.floor();
@@ -76,19 +140,19 @@ impl PerlinNoise {
         perlin_interpolation(c, u, v, w)
     }
 
-    pub fn turbulence(&self, p: &Point3, depth: i32) -> f64 {
-        let mut accum: f64 = 0.0;
-        let mut temp_p: Point3 = *p;
-        let mut weight: f64 = 1.0;
+    /// Fractal noise at p, around 0 and (with the usual gain) mostly between -1 and 1
+    pub fn fbm(&self, p: &Point3, fbm: &Fbm) -> f64 {
+        fbm.sum(p, |q| self.noise(q))
+    }
 
-        for _i in 0..depth {
-            accum += weight * self.noise(&temp_p);
-            weight *= 0.5;
-            // To do: implement mulassign for point3
-            temp_p = 2.0 * temp_p;
-        }
+    /// Absolute value of the fractal noise, which has sharp creases where it goes through zero
+    pub fn turbulence_with(&self, p: &Point3, fbm: &Fbm) -> f64 {
+        self.fbm(p, fbm).abs()
+    }
 
-        accum.abs()
+    /// Turbulence with depth octaves, each at double the frequency and half the amplitude
+    pub fn turbulence(&self, p: &Point3, depth: i32) -> f64 {
+        self.turbulence_with(p, &Fbm::new(depth.max(0) as u32))
     }
 }
 
@@ -7,8 +7,8 @@ use std::{
 // To do: Find and import rgb function. It is integrated in vscode and it lets you easily see the color
 //const RED = rgb(243, 27, 11);
 
-// To do: Make colors physical. Transfrom colors to frequencies for calculations and then transform them back to rgb for printing
-// Not trivial to do since the sum of two waves does not have a singular frequency. https://www.tandfonline.com/doi/abs/10.1080/10867651.1999.10487511
+// Colors are rgb. For physical colors the camera has a spectral mode (see spectrum.rs), where every sample follows
+// a single wavelength and rgb colors are turned into spectra: https://www.tandfonline.com/doi/abs/10.1080/10867651.1999.10487511
 
 // Define this alias
 // To do: make this a type why not
@@ -21,6 +21,15 @@ fn linear_to_gamma(linear_component: f64) -> f64 {
     0.0
 }
 
+/// Decode a component of an sRGB image (like a .png or .jpg texture) into linear light, with the exact sRGB curve
+pub fn srgb_to_linear(encoded: f64) -> f64 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
 impl Color {
     pub const fn black() -> Color {
         Color {
@@ -43,6 +52,19 @@ impl Color {
             z: 1.0,
         }
     }
+    /// Perceived brightness of a linear rgb color, with the weights of the sRGB primaries
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
+    }
+}
+
+/// Convert a color in the CIE XYZ space to linear rgb, with the sRGB primaries and a D65 white point
+pub fn xyz_to_rgb(xyz: Point3) -> Color {
+    Color {
+        x: 3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        y: -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        z: 0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    }
 }
 
 /// Process the color from linear space rgb to u8 rgb by tranforming from linear to gamma, clamping it to the 0..1 range and mutipling it by 256.
@@ -222,6 +222,19 @@ pub fn unit_vector(u: Point3) -> Point3 {
     u / u.length()
 }
 
+/// Returns two unit vectors that together with the unit vector n form an orthonormal basis.
+/// Branchless construction from: https://graphics.pixar.com/library/OrthonormalB/paper.pdf
+pub fn orthonormal_basis(n: &Vector3) -> (Vector3, Vector3) {
+    let sign: f64 = 1.0_f64.copysign(n.z);
+    let a: f64 = -1.0 / (sign + n.z);
+    let b: f64 = n.x * n.y * a;
+
+    (
+        Vector3::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x),
+        Vector3::new(b, sign + n.y * n.y * a, -n.y),
+    )
+}
+
 // Functions for random vectors
 // To do: initialize a local rng handle for better performance
 
@@ -293,3 +306,4 @@ pub fn refract(uv: Point3, n: Point3, eta_inital_over_eta_final: f64) -> Point3
 }
 
 pub mod color;
+pub mod spectrum;
@@ -0,0 +1,201 @@
+use std::sync::LazyLock;
+
+use super::Point3;
+use crate::point3::color::{Color, xyz_to_rgb};
+
+// Functions to go from light described by its spectrum (power per wavelength) to the colors used by the rest of the renderer
+
+/// Shortest wavelength of visible light considered, in nanometers
+pub const WAVELENGTH_MIN: f64 = 360.0;
+/// Longest wavelength of visible light considered, in nanometers
+pub const WAVELENGTH_MAX: f64 = 830.0;
+
+/// Piecewise gaussian used by the fit of the color matching functions
+fn piecewise_gaussian(x: f64, mu: f64, sigma_left: f64, sigma_right: f64) -> f64 {
+    let sigma: f64 = if x < mu { sigma_left } else { sigma_right };
+    let t: f64 = (x - mu) / sigma;
+    (-0.5 * t * t).exp()
+}
+
+/// The CIE 1931 color matching functions at a wavelength in nanometers, returned as (x̄, ȳ, z̄).
+/// Uses the multi-lobe fit from Wyman, Sloan and Shirley (2013): https://jcgt.org/published/0002/02/01/
+pub fn cie_xyz(wavelength: f64) -> Point3 {
+    Point3 {
+        x: 1.056 * piecewise_gaussian(wavelength, 599.8, 37.9, 31.0)
+            + 0.362 * piecewise_gaussian(wavelength, 442.0, 16.0, 26.7)
+            - 0.065 * piecewise_gaussian(wavelength, 501.1, 20.4, 26.2),
+        y: 0.821 * piecewise_gaussian(wavelength, 568.8, 46.9, 40.5)
+            + 0.286 * piecewise_gaussian(wavelength, 530.9, 16.3, 31.1),
+        z: 1.217 * piecewise_gaussian(wavelength, 437.0, 11.8, 36.0)
+            + 0.681 * piecewise_gaussian(wavelength, 459.0, 26.0, 13.8),
+    }
+}
+
+/// Planck's law: spectral radiance of a black body at temperature (in Kelvin) for a wavelength in nanometers.
+/// The result is in W/(m²·sr·nm)
+pub fn planck(wavelength: f64, temperature: f64) -> f64 {
+    const PLANCK: f64 = 6.62607015e-34;
+    const SPEED_OF_LIGHT: f64 = 2.99792458e8;
+    const BOLTZMANN: f64 = 1.380649e-23;
+
+    if temperature <= 0.0 {
+        return 0.0;
+    }
+
+    let lambda: f64 = wavelength * 1e-9;
+    let exponent: f64 = PLANCK * SPEED_OF_LIGHT / (lambda * BOLTZMANN * temperature);
+
+    // The extra 1e-9 takes the result from per meter to per nanometer
+    2.0 * PLANCK * SPEED_OF_LIGHT * SPEED_OF_LIGHT / (lambda.powi(5) * exponent.exp_m1()) * 1e-9
+}
+
+/// Spectrum of a black body divided by the one of a black body at 6500 K, which is close to the white of sRGB.
+/// Seen through a film where a flat spectrum is white, it has the same color as blackbody_color
+pub fn white_balanced_planck(wavelength: f64, temperature: f64) -> f64 {
+    planck(wavelength, temperature) / planck(wavelength, 6500.0)
+}
+
+/// Integrate a spectrum against the color matching functions, from WAVELENGTH_MIN to WAVELENGTH_MAX in steps of 1 nm
+pub fn spectrum_to_xyz<F: Fn(f64) -> f64>(spectrum: F) -> Point3 {
+    let steps: usize = (WAVELENGTH_MAX - WAVELENGTH_MIN) as usize;
+
+    (0..steps)
+        .map(|i| {
+            let wavelength: f64 = WAVELENGTH_MIN + i as f64 + 0.5;
+            spectrum(wavelength) * cie_xyz(wavelength)
+        })
+        .sum()
+}
+
+/// Linear rgb color of a black body at temperature (in Kelvin), scaled so that its luminance is 1.0.
+/// Colors outside of the sRGB gamut (very low temperatures) are clamped
+pub fn blackbody_color(temperature: f64) -> Color {
+    let xyz: Point3 = spectrum_to_xyz(|wavelength| planck(wavelength, temperature));
+
+    if xyz.y <= 0.0 {
+        return Color::black();
+    }
+
+    let rgb: Color = xyz_to_rgb(xyz / xyz.y);
+    Color::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+}
+
+/// Luminance of a black body at temperature relative to one at 6500 K, which is close to daylight.
+/// Grows roughly with the fourth power of the temperature, like the total emitted power (Stefan-Boltzmann law)
+pub fn blackbody_relative_luminance(temperature: f64) -> f64 {
+    let luminance = |t: f64| -> f64 { spectrum_to_xyz(|wavelength| planck(wavelength, t)).y };
+
+    luminance(temperature) / luminance(6500.0)
+}
+
+/// Integral over the visible wavelengths of the rgb color of each one (without the negative parts)
+static WAVELENGTH_RGB_INTEGRAL: LazyLock<Color> = LazyLock::new(|| {
+    let steps: usize = (WAVELENGTH_MAX - WAVELENGTH_MIN) as usize;
+
+    (0..steps)
+        .map(|i| wavelength_rgb(WAVELENGTH_MIN + i as f64 + 0.5))
+        .sum()
+});
+
+/// Linear rgb color of light of a single wavelength, with the colors outside of the sRGB gamut clamped
+fn wavelength_rgb(wavelength: f64) -> Color {
+    let rgb: Color = xyz_to_rgb(cie_xyz(wavelength));
+    Color::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+}
+
+/// Choose a wavelength for a path, uniformly among the visible ones
+pub fn sample_wavelength() -> f64 {
+    rand::random_range(WAVELENGTH_MIN..WAVELENGTH_MAX)
+}
+
+/// The part of white light that a path of a single wavelength carries, divided by the probability of
+/// sample_wavelength choosing it. Averaged over many paths it gives back white
+pub fn wavelength_weight(wavelength: f64) -> Color {
+    let integral: Color = *WAVELENGTH_RGB_INTEGRAL;
+    let rgb: Color = wavelength_rgb(wavelength) * (WAVELENGTH_MAX - WAVELENGTH_MIN);
+
+    Color::new(rgb.x / integral.x, rgb.y / integral.y, rgb.z / integral.z)
+}
+
+/// Spectra of the basis colors of Smits' method, sampled in 10 bins between 380 and 720 nm
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// Value at wavelength (in nanometers) of a smooth spectrum that looks like the linear rgb color.
+/// Uses the method of Smits (1999): https://www.cs.utah.edu/~bes/papers/color/
+pub fn rgb_to_spectrum(color: &Color, wavelength: f64) -> f64 {
+    let bin: usize = (((wavelength - 380.0) / 34.0).max(0.0) as usize).min(9);
+    let (r, g, b) = (color.x.max(0.0), color.y.max(0.0), color.z.max(0.0));
+
+    // The smallest component is white, the middle one goes into the secondary color between the two
+    // biggest and the rest into the primary color of the biggest
+    if r <= g && r <= b {
+        if g <= b {
+            r * SMITS_WHITE[bin] + (g - r) * SMITS_CYAN[bin] + (b - g) * SMITS_BLUE[bin]
+        } else {
+            r * SMITS_WHITE[bin] + (b - r) * SMITS_CYAN[bin] + (g - b) * SMITS_GREEN[bin]
+        }
+    } else if g <= r && g <= b {
+        if r <= b {
+            g * SMITS_WHITE[bin] + (r - g) * SMITS_MAGENTA[bin] + (b - r) * SMITS_BLUE[bin]
+        } else {
+            g * SMITS_WHITE[bin] + (b - g) * SMITS_MAGENTA[bin] + (r - b) * SMITS_RED[bin]
+        }
+    } else if r <= g {
+        b * SMITS_WHITE[bin] + (r - b) * SMITS_YELLOW[bin] + (g - r) * SMITS_GREEN[bin]
+    } else {
+        b * SMITS_WHITE[bin] + (g - b) * SMITS_YELLOW[bin] + (r - g) * SMITS_RED[bin]
+    }
+}
+
+/// Linear rgb of a flat spectrum of value 1, used as the white point of the film
+static FLAT_SPECTRUM_RGB: LazyLock<Color> = LazyLock::new(|| xyz_to_rgb(spectrum_to_xyz(|_| 1.0)));
+
+/// The XYZ color that a single sample of radiance (at a wavelength chosen by sample_wavelength) adds to the film
+pub fn wavelength_to_film_xyz(radiance: f64, wavelength: f64) -> Point3 {
+    radiance * (WAVELENGTH_MAX - WAVELENGTH_MIN) * cie_xyz(wavelength)
+}
+
+/// Convert the XYZ collected by the film to linear rgb, white balanced so that a flat spectrum (what white
+/// colors are turned into) gives back white
+pub fn film_xyz_to_rgb(xyz: Point3) -> Color {
+    let rgb: Color = xyz_to_rgb(xyz);
+    let white: Color = *FLAT_SPECTRUM_RGB;
+
+    Color::new(rgb.x / white.x, rgb.y / white.y, rgb.z / white.z)
+}
+
+/// For quantities measured at the wavelengths of red, green and blue (650, 550 and 450 nm), like the refractive index
+/// of the metal presets: interpolate linearly to get their value at any wavelength
+pub fn interpolate_rgb_samples(samples: &Color, wavelength: f64) -> f64 {
+    if wavelength <= 450.0 {
+        samples.z
+    } else if wavelength <= 550.0 {
+        let t: f64 = (wavelength - 450.0) / 100.0;
+        samples.z + t * (samples.y - samples.z)
+    } else if wavelength <= 650.0 {
+        let t: f64 = (wavelength - 550.0) / 100.0;
+        samples.y + t * (samples.x - samples.y)
+    } else {
+        samples.x
+    }
+}
@@ -1,10 +1,21 @@
-use crate::{Point3, point3::Vector3};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{Point3, point3::Vector3, point3::color::Color};
 
 /// Ray is a geometrical line: \vec{A} + \vec{B}t
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vector3,
     pub inverse_direction: Vector3,
+    /// Wavelength in nanometers that the path carries, once a dispersive material has split the light.
+    /// None if the ray carries all the colors
+    pub wavelength: Option<f64>,
+    /// The media the ray is travelling through, once a material has changed them. None if the ray is in the same
+    /// media as the ray that created it. Shared, as most rays never change it
+    pub media: Option<Arc<MediumStack>>,
+    /// The cone of directions the ray stands for, to filter the textures it hits. None if it is not known
+    pub cone: Option<RayCone>,
 }
 
 impl Ray {
@@ -16,7 +27,110 @@ impl Ray {
             origin,
             direction,
             inverse_direction: Point3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z),
+            wavelength: None,
+            media: None,
+            cone: None,
         }
         // Ray { origin, direction }
     }
+    pub fn with_wavelength(mut self, wavelength: Option<f64>) -> Ray {
+        self.wavelength = wavelength;
+        self
+    }
+    pub fn with_media(mut self, media: Option<Arc<MediumStack>>) -> Ray {
+        self.media = media;
+        self
+    }
+    pub fn with_cone(mut self, cone: Option<RayCone>) -> Ray {
+        self.cone = cone;
+        self
+    }
+}
+
+/// A ray seen as a cone (Akenine-Möller et al. 2019, "Texture Level of Detail Strategies for Real-Time Ray
+/// Tracing"): a camera ray covers a pixel, which gets bigger the further away it goes
+#[derive(Clone, Copy, Debug)]
+pub struct RayCone {
+    /// Width at the origin of the ray
+    pub width: f64,
+    /// Angle between the sides of the cone, in radians
+    pub spread: f64,
+}
+
+impl RayCone {
+    /// Width after travelling distance
+    pub fn width_at(&self, distance: f64) -> f64 {
+        self.width + self.spread * distance
+    }
+}
+
+/// Maximum number of media a ray can be inside of at the same time
+pub const MAX_NESTED_MEDIA: usize = 8;
+
+/// A new id for a medium, different from all the ones given before. Materials that rays can go into take one when
+/// they are created, so every object that shares the material is the same medium, wherever the material is moved to
+pub fn next_medium_id() -> usize {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A medium that a ray went into, like the water of a glass or an ice cube floating in it
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct MediumEntry {
+    /// Identifies the material, so the ray knows which medium it leaves
+    pub id: usize,
+    /// Where media overlap, the one with the biggest priority is the one that is really there
+    pub priority: u32,
+    pub refraction_index: f64,
+    /// Absorption coefficient, per unit of distance and for each color channel
+    pub absorption: Color,
+}
+
+/// The media a ray is inside of, in the order it went into them. It lets nested objects (ice in water, water in a
+/// glass) know the refractive index on each side of a surface, and overlapping ones ignore the surfaces that are
+/// inside of a medium with a bigger priority. Rays only point to it, and a new one is made when a dielectric changes
+/// the media, so that the rays that don't change it stay small
+#[derive(Clone, Copy, Default, Debug)]
+pub struct MediumStack {
+    entries: [MediumEntry; MAX_NESTED_MEDIA],
+    length: usize,
+}
+
+impl MediumStack {
+    /// Add a medium that the ray goes into. Media past the maximum are ignored
+    pub fn push(&mut self, entry: MediumEntry) {
+        if self.length < MAX_NESTED_MEDIA {
+            self.entries[self.length] = entry;
+            self.length += 1;
+        }
+    }
+
+    /// Remove the medium with id, returning whether the ray was inside of it
+    pub fn remove(&mut self, id: usize) -> bool {
+        match self.entries[..self.length].iter().rposition(|e| e.id == id) {
+            Some(index) => {
+                self.entries.copy_within(index + 1..self.length, index);
+                self.length -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.entries[..self.length].iter().any(|e| e.id == id)
+    }
+
+    /// The medium the ray is really in: the one with the biggest priority, or the last one it went into if several
+    /// have it. None if it is in air (or vacuum)
+    pub fn current(&self) -> Option<MediumEntry> {
+        self.entries[..self.length]
+            .iter()
+            .copied()
+            .reduce(|a, b| if b.priority >= a.priority { b } else { a })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
 }
@@ -1,3 +1,29 @@
+/// A hit at p with a white Lambertian material and no surface coordinates, for tests that only need the point,
+/// the normal and the side. Hittables fill in the rest when it is passed to hit
+#[cfg(test)]
+pub fn record_at(
+    p: crate::point3::Point3,
+    normal: crate::point3::Vector3,
+    front_face: bool,
+) -> crate::hittable::HitRecord {
+    use crate::hittable::{HitRecord, SurfaceCoordinate};
+    use crate::material::Lambertian;
+    use crate::point3::Vector3;
+    use crate::point3::color::Color;
+
+    HitRecord {
+        p,
+        normal,
+        material: Lambertian::from_color(Color::white()),
+        t: 1.0,
+        surface_coords: SurfaceCoordinate { u: 0.0, v: 0.0 },
+        front_face,
+        dp_du: Vector3::default(),
+        dp_dv: Vector3::default(),
+        footprint: SurfaceCoordinate { u: 0.0, v: 0.0 },
+    }
+}
+
 #[cfg(test)]
 mod point3 {
     use crate::point3::{Point3, cross, dot};
@@ -153,3 +179,1691 @@ mod point3 {
         assert_eq!(p1.length(), 5.0)
     }
 }
+#[cfg(test)]
+mod distribution {
+    use crate::light::distribution::{Distribution1D, Distribution2D};
+    #[test]
+    fn samples_follow_function() {
+        let distribution: Distribution1D = Distribution1D::new(vec![0.0, 1.0, 3.0, 0.0]);
+
+        // A quarter of the mass is in the second interval and the rest in the third
+        let (x, pdf, offset) = distribution.sample_continuous(0.1);
+        assert_eq!(offset, 1);
+        assert!((0.25..0.5).contains(&x));
+        assert_eq!(pdf, 1.0);
+
+        let (x, pdf, offset) = distribution.sample_continuous(0.5);
+        assert_eq!(offset, 2);
+        assert!((0.5..0.75).contains(&x));
+        assert_eq!(pdf, 3.0);
+
+        assert_eq!(distribution.pdf(0.1), 0.0);
+    }
+    #[test]
+    fn black_falls_back_to_uniform() {
+        let distribution: Distribution1D = Distribution1D::new(vec![0.0; 8]);
+        let (x, pdf, _) = distribution.sample_continuous(0.3);
+
+        assert!((x - 0.3).abs() < 1e-10);
+        assert_eq!(pdf, 1.0);
+    }
+    #[test]
+    fn pdf_2d_integrates_to_one() {
+        let function: Vec<f64> = vec![1.0, 2.0, 0.0, 5.0, 0.0, 0.0, 0.5, 4.0, 3.0];
+        let distribution: Distribution2D = Distribution2D::new(&function, 3, 3);
+
+        let mut integral: f64 = 0.0;
+        for j in 0..3 {
+            for i in 0..3 {
+                integral += distribution.pdf((i as f64 + 0.5) / 3.0, (j as f64 + 0.5) / 3.0) / 9.0;
+            }
+        }
+        assert!((integral - 1.0).abs() < 1e-10);
+
+        let (s, t, pdf) = distribution.sample_continuous(0.7, 0.2);
+        assert!((distribution.pdf(s, t) - pdf).abs() < 1e-10);
+    }
+}
+#[cfg(test)]
+mod sky {
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    use crate::light::LightSample;
+    use crate::light::environment::Environment;
+    use crate::light::sky::{PhysicalSky, SUN_ANGULAR_RADIUS_DEGREES};
+    use crate::point3::{Point3, Vector3, dot, orthonormal_basis, unit_vector};
+
+    /// Integral of the pdf over the sphere, in rings around the sun. The rings inside the sun disk are counted apart,
+    /// as it is far too small to be found by evenly spaced ones
+    fn pdf_integral(sky: &PhysicalSky) -> f64 {
+        let axis: Vector3 = sky.sun_direction();
+        let (tangent, bitangent) = orthonormal_basis(&axis);
+        let cos_radius: f64 = SUN_ANGULAR_RADIUS_DEGREES.to_radians().cos();
+
+        let integrate = |cos_from: f64, cos_to: f64, rings: usize| -> f64 {
+            let steps: usize = 256;
+            let ring_width: f64 = (cos_to - cos_from) / rings as f64;
+            let mut integral: f64 = 0.0;
+            for i in 0..rings {
+                let cos_theta: f64 = cos_from + (i as f64 + 0.5) * ring_width;
+                let sin_theta: f64 = (1.0 - cos_theta * cos_theta).sqrt();
+                for j in 0..steps {
+                    let phi: f64 = 2.0 * PI * (j as f64 + 0.5) / steps as f64;
+                    let direction: Vector3 = sin_theta * phi.cos() * tangent
+                        + sin_theta * phi.sin() * bitangent
+                        + cos_theta * axis;
+                    integral += sky.pdf(&direction) * ring_width * 2.0 * PI / steps as f64;
+                }
+            }
+            integral
+        };
+
+        integrate(-1.0, cos_radius, 2000) + integrate(cos_radius, 1.0, 4)
+    }
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        let sky: Arc<PhysicalSky> = PhysicalSky::new(35.0, 60.0, 3.0);
+
+        assert!((pdf_integral(&sky) - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn samples_match_value_and_pdf() {
+        let sky: Arc<PhysicalSky> = PhysicalSky::new(35.0, 60.0, 3.0);
+        let cos_radius: f64 = SUN_ANGULAR_RADIUS_DEGREES.to_radians().cos();
+
+        let mut sun_samples: u32 = 0;
+        for _ in 0..2000 {
+            let mut light_sample: LightSample = LightSample::default();
+            if !sky.sample(&mut light_sample) {
+                continue;
+            }
+            let direction: Vector3 = light_sample.direction;
+
+            assert!((direction.length() - 1.0).abs() < 1e-9);
+            assert_eq!(light_sample.distance, f64::INFINITY);
+            assert_eq!(light_sample.pdf, sky.pdf(&direction));
+            assert_eq!(light_sample.radiance, sky.value(&direction));
+            if dot(&direction, &sky.sun_direction()) >= cos_radius {
+                sun_samples += 1;
+            }
+        }
+        // Half of the samples go to the sun
+        assert!((800..1200).contains(&sun_samples));
+    }
+
+    #[test]
+    fn no_sun_below_the_horizon() {
+        let sky: Arc<PhysicalSky> = PhysicalSky::new(-10.0, 60.0, 3.0);
+        let sun: Vector3 = sky.sun_direction();
+
+        // Only the sky is sampled, and the ground gets nothing
+        assert!((pdf_integral(&sky) - 1.0).abs() < 1e-3);
+        assert_eq!(sky.pdf(&sun), 0.0);
+        assert_eq!(sky.value(&sun), Point3::default());
+
+        // The sky above the sun has no bright disk in it
+        let above: Vector3 = unit_vector(Point3::new(sun.x, 0.2, sun.z));
+        let near_above: Vector3 = unit_vector(Point3::new(sun.x, 0.21, sun.z));
+        let value: Point3 = sky.value(&above);
+        assert!(value.x >= 0.0 && value.y >= 0.0 && value.z >= 0.0);
+        assert!((value - sky.value(&near_above)).length() < 0.1 * value.length().max(1e-3));
+
+        for _ in 0..200 {
+            let mut light_sample: LightSample = LightSample::default();
+            assert!(sky.sample(&mut light_sample));
+            assert!(light_sample.direction.y >= 0.0);
+            assert!(dot(&light_sample.direction, &sun) < 0.99);
+        }
+    }
+}
+
+#[cfg(test)]
+mod image_environment {
+    use std::f64::consts::PI;
+
+    use image::{ImageBuffer, Rgb, Rgb32FImage};
+
+    use crate::light::LightSample;
+    use crate::light::environment::{Environment, ImageEnvironment};
+    use crate::point3::Vector3;
+
+    /// A gradient along the columns with a small bright spot near the top, away from the middle of the image
+    fn environment() -> ImageEnvironment {
+        let image: Rgb32FImage = ImageBuffer::from_fn(32, 16, |i, j| {
+            let value: f32 = if (i, j) == (5, 3) {
+                50.0
+            } else {
+                0.1 + i as f32 / 32.0
+            };
+            Rgb([value, 0.5 * value, 0.25])
+        });
+
+        ImageEnvironment::new(image, 1.0)
+    }
+
+    #[test]
+    fn samples_match_value_and_pdf() {
+        let environment: ImageEnvironment = environment();
+
+        for _ in 0..2000 {
+            let mut light_sample: LightSample = LightSample::default();
+            assert!(environment.sample(&mut light_sample));
+            let direction: Vector3 = light_sample.direction;
+
+            assert!((direction.length() - 1.0).abs() < 1e-9);
+            assert_eq!(light_sample.distance, f64::INFINITY);
+            assert!((light_sample.radiance - environment.value(&direction)).length() < 1e-9);
+            let pdf: f64 = environment.pdf(&direction);
+            assert!((light_sample.pdf - pdf).abs() < 1e-6 * pdf);
+        }
+    }
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        let environment: ImageEnvironment = environment();
+
+        // Monte Carlo estimate with a random direction in each of a grid of cells of equal area, which are even in
+        // the height and the angle around the vertical
+        let (rings, steps): (usize, usize) = (256, 512);
+        let mut integral: f64 = 0.0;
+        for i in 0..rings {
+            for j in 0..steps {
+                let y: f64 = -1.0 + 2.0 * (i as f64 + rand::random::<f64>()) / rings as f64;
+                let phi: f64 = 2.0 * PI * (j as f64 + rand::random::<f64>()) / steps as f64;
+                let radius: f64 = (1.0 - y * y).sqrt();
+                let direction: Vector3 = Vector3::new(radius * phi.cos(), y, radius * phi.sin());
+
+                integral += environment.pdf(&direction) * 4.0 * PI / (rings * steps) as f64;
+            }
+        }
+
+        assert!((integral - 1.0).abs() < 0.01);
+    }
+}
+
+#[cfg(test)]
+mod delta_lights {
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    use crate::light::delta::{DirectionalLight, PointLight, SpotLight};
+    use crate::light::{Light, LightSample};
+    use crate::point3::color::Color;
+    use crate::point3::{Point3, Vector3};
+
+    /// The spot light at height 2 pointing down, seen from the ground at angle degrees from its axis
+    fn spot_sample(spot: &SpotLight, angle: f64) -> Option<LightSample> {
+        let p: Point3 = Point3::new(2.0 * angle.to_radians().tan(), 0.0, 0.0);
+        let mut light_sample: LightSample = LightSample::default();
+
+        spot.sample(&p, &mut light_sample).then_some(light_sample)
+    }
+
+    #[test]
+    fn point_light_falls_off_with_the_square_of_distance() {
+        let light: Arc<PointLight> =
+            PointLight::new(Point3::new(0.0, 4.0, 0.0), Color::white(), 100.0);
+
+        let mut near: LightSample = LightSample::default();
+        let mut far: LightSample = LightSample::default();
+        assert!(light.sample(&Point3::new(0.0, 3.0, 0.0), &mut near));
+        assert!(light.sample(&Point3::new(0.0, 2.0, 0.0), &mut far));
+
+        assert_eq!(near.distance, 1.0);
+        assert_eq!(near.direction, Point3::new(0.0, 1.0, 0.0));
+        assert_eq!(near.pdf, 1.0);
+        assert!((near.radiance.x - 100.0 / (4.0 * PI)).abs() < 1e-12);
+        assert!((far.radiance.x - near.radiance.x / 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn spot_light_fades_between_its_cones() {
+        let spot: Arc<SpotLight> = SpotLight::new(
+            Point3::new(0.0, 2.0, 0.0),
+            Point3::default(),
+            Color::white(),
+            50.0,
+            20.0,
+            40.0,
+        );
+        let full: f64 = spot_sample(&spot, 0.0).unwrap().radiance.x;
+
+        // Inside the inner cone only the distance changes the light
+        let inner: LightSample = spot_sample(&spot, 15.0).unwrap();
+        let distance_squared: f64 = inner.distance * inner.distance;
+        assert!((inner.radiance.x * distance_squared / 4.0 - full).abs() < 1e-9);
+
+        // Outside the outer cone there is no light at all
+        assert!(spot_sample(&spot, 45.0).is_none());
+
+        // In between, the intensity goes down smoothly from full to nothing
+        let mut previous: f64 = 1.0;
+        for i in 1..40 {
+            let angle: f64 = 20.0 + 0.5 * i as f64;
+            let sample: LightSample = spot_sample(&spot, angle).unwrap();
+            let fraction: f64 =
+                sample.radiance.x * sample.distance * sample.distance / (4.0 * full);
+
+            assert!(fraction > 0.0 && fraction < previous);
+            assert!(previous - fraction < 0.06);
+            previous = fraction;
+        }
+        assert!(previous < 0.01);
+    }
+
+    #[test]
+    fn lights_do_not_reach_their_own_position() {
+        let position: Point3 = Point3::new(0.0, 2.0, 0.0);
+        let point: Arc<PointLight> = PointLight::new(position, Color::white(), 100.0);
+        let spot: Arc<SpotLight> = SpotLight::new(
+            position,
+            Point3::default(),
+            Color::white(),
+            50.0,
+            20.0,
+            40.0,
+        );
+
+        let mut light_sample: LightSample = LightSample::default();
+        assert!(!point.sample(&position, &mut light_sample));
+        assert!(!spot.sample(&position, &mut light_sample));
+    }
+
+    #[test]
+    fn directional_light_is_infinitely_far() {
+        let light: Arc<DirectionalLight> =
+            DirectionalLight::new(Vector3::new(0.0, -2.0, 0.0), Color::white(), 3.0);
+
+        for p in [Point3::default(), Point3::new(100.0, -5.0, 7.0)] {
+            let mut light_sample: LightSample = LightSample::default();
+            assert!(light.sample(&p, &mut light_sample));
+
+            assert_eq!(light_sample.direction, Vector3::new(0.0, 1.0, 0.0));
+            assert_eq!(light_sample.distance, f64::INFINITY);
+            assert_eq!(light_sample.radiance, Color::new(3.0, 3.0, 3.0));
+            assert_eq!(light_sample.pdf, 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod diffuse_light {
+    use std::sync::Arc;
+
+    use super::record_at;
+    use crate::hittable::HitRecord;
+    use crate::material::{DiffuseLight, Material};
+    use crate::point3::color::Color;
+    use crate::point3::{Point3, Vector3};
+    use crate::texture::SolidColor;
+
+    #[test]
+    fn one_sided_lights_only_emit_from_the_front() {
+        let color: Color = Color::new(0.2, 0.5, 1.0);
+        let one_sided: Arc<DiffuseLight> = DiffuseLight::new(SolidColor::new(color), 4.0, false);
+        let two_sided: Arc<DiffuseLight> = DiffuseLight::new(SolidColor::new(color), 4.0, true);
+
+        let front: HitRecord = record_at(Point3::default(), Vector3::new(0.0, 1.0, 0.0), true);
+        let back: HitRecord = record_at(Point3::default(), Vector3::new(0.0, 1.0, 0.0), false);
+
+        // The intensity multiplies the color of the texture
+        assert_eq!(one_sided.emitted(&front), Color::new(0.8, 2.0, 4.0));
+        assert_eq!(one_sided.emitted(&back), Color::black());
+        assert_eq!(two_sided.emitted(&front), Color::new(0.8, 2.0, 4.0));
+        assert_eq!(two_sided.emitted(&back), Color::new(0.8, 2.0, 4.0));
+
+        assert_eq!(DiffuseLight::from_color(color).emitted(&back), color);
+    }
+}
+
+#[cfg(test)]
+mod spectrum {
+    use crate::material::dispersion::Dispersion;
+    use crate::point3::Point3;
+    use crate::point3::color::Color;
+    use crate::point3::spectrum::{
+        WAVELENGTH_MAX, WAVELENGTH_MIN, blackbody_color, blackbody_relative_luminance,
+        film_xyz_to_rgb, rgb_to_spectrum, spectrum_to_xyz, wavelength_weight,
+    };
+
+    #[test]
+    fn daylight_is_near_white() {
+        let color: Color = blackbody_color(6500.0);
+
+        assert!((color.x - 1.0).abs() < 0.1);
+        assert!((color.y - 1.0).abs() < 0.1);
+        assert!((color.z - 1.0).abs() < 0.1);
+    }
+    #[test]
+    fn incandescent_is_warm() {
+        let color: Color = blackbody_color(2700.0);
+
+        assert!(color.x > color.y && color.y > color.z);
+        assert!((color.luminance() - 1.0).abs() < 0.05);
+    }
+    #[test]
+    fn hotter_is_brighter() {
+        assert!((blackbody_relative_luminance(6500.0) - 1.0).abs() < 1e-10);
+        assert!(blackbody_relative_luminance(3000.0) < blackbody_relative_luminance(5000.0));
+    }
+    #[test]
+    fn wavelength_weights_average_to_white() {
+        let steps: usize = 4700;
+        let mut average: Color = Color::black();
+        for i in 0..steps {
+            let wavelength: f64 = WAVELENGTH_MIN
+                + (i as f64 + 0.5) / steps as f64 * (WAVELENGTH_MAX - WAVELENGTH_MIN);
+            average = average + wavelength_weight(wavelength) / steps as f64;
+        }
+
+        assert!((average.x - 1.0).abs() < 1e-3);
+        assert!((average.y - 1.0).abs() < 1e-3);
+        assert!((average.z - 1.0).abs() < 1e-3);
+    }
+    #[test]
+    fn glass_disperses_blue_more() {
+        let bk7: Dispersion = Dispersion::bk7();
+        assert!((bk7.refraction_index(587.6) - 1.5168).abs() < 1e-4);
+        assert!(bk7.refraction_index(450.0) > bk7.refraction_index(650.0));
+        assert!((Dispersion::diamond().refraction_index(589.0) - 2.417).abs() < 2e-3);
+
+        // The Abbe number is recovered from the fitted formula
+        let fitted: Dispersion = Dispersion::from_abbe_number(1.5168, 64.17);
+        let abbe_number: f64 = (fitted.refraction_index(587.6) - 1.0)
+            / (fitted.refraction_index(486.1) - fitted.refraction_index(656.3));
+        assert!((fitted.refraction_index(587.6) - 1.5168).abs() < 1e-10);
+        assert!((abbe_number - 64.17).abs() < 1e-6);
+    }
+    #[test]
+    fn rgb_survives_spectral_round_trip() {
+        for color in [
+            Color::white(),
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.2, 0.5, 0.8),
+            Color::new(0.9, 0.7, 0.1),
+        ] {
+            let xyz: Point3 = spectrum_to_xyz(|wavelength| rgb_to_spectrum(&color, wavelength));
+            let rgb: Color = film_xyz_to_rgb(xyz);
+
+            assert!((rgb.x - color.x).abs() < 0.02);
+            assert!((rgb.y - color.y).abs() < 0.02);
+            assert!((rgb.z - color.z).abs() < 0.02);
+        }
+    }
+}
+#[cfg(test)]
+mod microfacet {
+    use std::f64::consts::PI;
+
+    use std::sync::Arc;
+
+    use super::record_at;
+    use crate::hittable::HitRecord;
+    use crate::material::coated::Coated;
+    use crate::material::conductor::Conductor;
+    use crate::material::microfacet::{Ggx, fresnel_conductor, fresnel_dielectric};
+    use crate::material::mix::MixMaterial;
+    use crate::material::oren_nayar::OrenNayar;
+    use crate::material::principled::Principled;
+    use crate::material::rough_dielectric::RoughDielectric;
+    use crate::material::{
+        Lambertian, Material, ScatteredRayAndAttenuation, absorption_from_color,
+        transmittance_inside,
+    };
+    use crate::point3::color::Color;
+    use crate::point3::{Point3, Vector3, dot};
+    use crate::ray::Ray;
+
+    #[test]
+    fn distribution_is_normalized() {
+        // The projected area of the microfacets must add up to the area of the surface
+        let ggx: Ggx = Ggx::from_roughness(0.5);
+        let steps: usize = 2000;
+
+        let mut integral: f64 = 0.0;
+        for i in 0..steps {
+            let theta: f64 = (i as f64 + 0.5) / steps as f64 * 0.5 * PI;
+            let h: Vector3 = Point3::new(theta.sin(), 0.0, theta.cos());
+            integral += ggx.distribution(&h)
+                * theta.cos()
+                * theta.sin()
+                * 2.0
+                * PI
+                * (0.5 * PI / steps as f64);
+        }
+        assert!((integral - 1.0).abs() < 1e-3);
+    }
+    #[test]
+    fn visible_normals_face_the_viewer() {
+        let ggx: Ggx = Ggx::from_roughness(0.8);
+        let wo: Vector3 = Point3::new(0.6, 0.0, 0.8);
+
+        for _ in 0..1000 {
+            let h: Vector3 = ggx.sample_visible_normal(
+                &wo,
+                rand::random_range(0.0..1.0),
+                rand::random_range(0.0..1.0),
+            );
+            assert!((h.length() - 1.0).abs() < 1e-10);
+            assert!(dot(&wo, &h) >= 0.0);
+            assert!(ggx.visible_normal_pdf(&wo, &h) > 0.0);
+        }
+    }
+    #[test]
+    fn conductor_reflects_more_at_grazing_angles() {
+        let eta: Color = Color::new(1.657, 0.880, 0.521);
+        let k: Color = Color::new(9.224, 6.270, 4.837);
+
+        let normal: Color = fresnel_conductor(1.0, &eta, &k);
+        let grazing: Color = fresnel_conductor(0.0, &eta, &k);
+
+        assert!(normal.x > 0.85 && normal.x < 1.0);
+        assert!((grazing.x - 1.0).abs() < 1e-10);
+    }
+    #[test]
+    fn dielectric_fresnel_limits() {
+        assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-10);
+        // Total internal reflection going from glass to air at a grazing angle
+        assert_eq!(fresnel_dielectric(0.2, 1.0 / 1.5), 1.0);
+    }
+    #[test]
+    fn rough_dielectric_sampling_matches_evaluate() {
+        check_sampling_matches_evaluate(RoughDielectric::new(1.5, 0.4));
+    }
+    /// Check that the attenuation and pdf given by scatter agree with evaluate and scattering_pdf
+    fn check_sampling_matches_evaluate(material: Arc<dyn Material>) {
+        let ray_in: Ray = Ray::new(Point3::new(-0.3, 0.0, 1.0), Point3::new(0.3, 0.0, -1.0));
+
+        for front_face in [true, false] {
+            let record: HitRecord =
+                record_at(Point3::default(), Point3::new(0.0, 0.0, 1.0), front_face);
+
+            for _ in 0..1000 {
+                let mut sca_att: ScatteredRayAndAttenuation = ScatteredRayAndAttenuation {
+                    scattered_ray: Ray::new(Point3::default(), Point3::new(0.0, 0.0, 1.0)),
+                    attenuation: Color::black(),
+                    pdf: 0.0,
+                };
+                if !material.scatter(&ray_in, &record, &mut sca_att) || sca_att.pdf == 0.0 {
+                    continue;
+                }
+                let direction: Vector3 = sca_att.scattered_ray.direction;
+                let pdf: f64 = material.scattering_pdf(&ray_in, &record, &direction);
+                let value: Color = material.evaluate(&ray_in, &record, &direction);
+
+                assert!((pdf - sca_att.pdf).abs() < 1e-6 * pdf.max(1.0));
+                assert!((value.y / pdf - sca_att.attenuation.y).abs() < 1e-6);
+            }
+        }
+    }
+    #[test]
+    fn principled_sampling_matches_evaluate() {
+        let materials: [Principled; 3] = [
+            Principled::from_color(Color::new(0.8, 0.3, 0.2)).with_sheen(0.5, 0.5),
+            Principled::from_color(Color::new(0.9, 0.6, 0.3))
+                .with_metallic(0.7)
+                .with_clearcoat(1.0, 0.5),
+            Principled::from_color(Color::new(0.7, 0.9, 0.8))
+                .with_roughness(0.3)
+                .with_transmission(0.8, 1.45),
+        ];
+
+        for material in materials {
+            check_sampling_matches_evaluate(Arc::new(material));
+        }
+    }
+    #[test]
+    fn mixed_and_coated_sampling_matches_evaluate() {
+        let diffuse: Arc<Lambertian> = Lambertian::from_color(Color::new(0.8, 0.3, 0.2));
+
+        check_sampling_matches_evaluate(MixMaterial::new(
+            diffuse.clone(),
+            Conductor::copper(0.4),
+            0.3,
+        ));
+        check_sampling_matches_evaluate(Coated::new(diffuse.clone(), 1.5, 0.2));
+        // A polished coat only adds specular bounces, the rest must still match
+        check_sampling_matches_evaluate(Coated::tinted(
+            diffuse,
+            1.5,
+            0.0,
+            Color::new(0.9, 0.7, 0.3),
+        ));
+    }
+    #[test]
+    fn oren_nayar_without_roughness_is_lambertian() {
+        let color: Color = Color::new(0.5, 0.4, 0.3);
+        let smooth: Arc<OrenNayar> = OrenNayar::from_color(color, 0.0);
+        let lambertian: Arc<Lambertian> = Lambertian::from_color(color);
+        let ray_in: Ray = Ray::new(Point3::new(-0.3, 0.0, 1.0), Point3::new(0.3, 0.0, -1.0));
+        let record: HitRecord = record_at(Point3::default(), Point3::new(0.0, 0.0, 1.0), true);
+
+        let direction: Vector3 = Point3::new(0.5, 0.2, 0.7);
+        let difference: Color = smooth.evaluate(&ray_in, &record, &direction)
+            - lambertian.evaluate(&ray_in, &record, &direction);
+        assert!(difference.length() < 1e-10);
+
+        check_sampling_matches_evaluate(OrenNayar::from_color(color, 30.0));
+    }
+    #[test]
+    fn absorption_follows_beer_lambert() {
+        let absorption: Color = absorption_from_color(Color::new(0.5, 1.0, 0.25), 1.0);
+        let ray_in: Ray = Ray::new(Point3::default(), Point3::new(0.0, 0.0, -1.0));
+        let mut record: HitRecord = record_at(
+            Point3::new(0.0, 0.0, -2.0),
+            Point3::new(0.0, 0.0, 1.0),
+            false,
+        );
+        record.t = 2.0;
+
+        // Twice the distance squares the color
+        let transmittance: Color = transmittance_inside(&absorption, &ray_in, &record);
+        assert!((transmittance.x - 0.25).abs() < 1e-10);
+        assert!((transmittance.y - 1.0).abs() < 1e-10);
+        assert!((transmittance.z - 0.0625).abs() < 1e-6);
+
+        // Rays arriving from outside have not gone through the material
+        record.front_face = true;
+        let transmittance: Color = transmittance_inside(&absorption, &ray_in, &record);
+        assert_eq!(transmittance.x, 1.0);
+    }
+}
+#[cfg(test)]
+mod tangent_frame {
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    use super::record_at;
+    use crate::hittable::sphere::{get_sphere_uv, sphere_derivatives};
+    use crate::hittable::triangle::Triangle;
+    use crate::hittable::{HitRecord, Hittable, SurfaceCoordinate};
+    use crate::material::normal_map::{NormalMapped, tangent_frame};
+    use crate::material::{Lambertian, Material};
+    use crate::point3::color::Color;
+    use crate::point3::{Point3, Vector3, dot, unit_vector};
+    use crate::ray::Ray;
+    use crate::texture::Texture;
+
+    #[test]
+    fn sphere_derivatives_follow_the_coordinates() {
+        let n: Vector3 = unit_vector(Point3::new(0.3, 0.5, -0.8));
+        let (dp_du, dp_dv) = sphere_derivatives(&n, 2.0);
+        assert!(dot(&dp_du, &n).abs() < 1e-10);
+        assert!(dot(&dp_dv, &n).abs() < 1e-10);
+
+        // Moving a small step along each derivative changes only its coordinate
+        let step: f64 = 1e-6;
+        let start: SurfaceCoordinate = get_sphere_uv(&n);
+        let along_u: SurfaceCoordinate = get_sphere_uv(&unit_vector(n + step * dp_du / 2.0));
+        let along_v: SurfaceCoordinate = get_sphere_uv(&unit_vector(n + step * dp_dv / 2.0));
+
+        assert!((along_u.u - start.u - step).abs() < 1e-9);
+        assert!((along_u.v - start.v).abs() < 1e-9);
+        assert!((along_v.v - start.v - step).abs() < 1e-9);
+        assert!((along_v.u - start.u).abs() < 1e-9);
+    }
+    #[test]
+    fn triangle_derivatives_follow_texture_coordinates() {
+        let triangle: Triangle = Triangle::from_vertex_locations(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(2.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Lambertian::from_color(Color::white()),
+        )
+        .with_texture_coordinates([
+            SurfaceCoordinate { u: 0.5, v: 0.0 },
+            SurfaceCoordinate { u: 0.5, v: 1.0 },
+            SurfaceCoordinate { u: 0.0, v: 0.0 },
+        ]);
+
+        let hit_at = |x: f64, y: f64| -> HitRecord {
+            let mut record: HitRecord = record_at(Point3::default(), Point3::default(), false);
+            let ray: Ray = Ray::new(Point3::new(x, y, 1.0), Point3::new(0.0, 0.0, -1.0));
+            assert!(triangle.hit(&ray, &(0.0..10.0), &mut record));
+            record
+        };
+
+        let record: HitRecord = hit_at(0.5, 0.25);
+        assert!((record.surface_coords.u - 0.375).abs() < 1e-10);
+        assert!((record.surface_coords.v - 0.25).abs() < 1e-10);
+
+        let step: f64 = 0.01;
+        let moved: HitRecord = hit_at(
+            record.p.x + step * record.dp_du.x,
+            record.p.y + step * record.dp_du.y,
+        );
+        assert!((moved.surface_coords.u - record.surface_coords.u - step).abs() < 1e-10);
+        assert!((moved.surface_coords.v - record.surface_coords.v).abs() < 1e-10);
+    }
+    #[test]
+    fn frame_is_orthonormal_on_skewed_coordinates() {
+        let normal: Vector3 = unit_vector(Vector3::new(0.1, 0.2, 1.0));
+        // Derivatives that are not perpendicular to each other nor to the normal, with v flipped
+        let dp_du: Vector3 = Vector3::new(2.0, 0.3, 0.5);
+        let dp_dv: Vector3 = Vector3::new(1.0, -1.5, 0.2);
+
+        let (tangent, bitangent): (Vector3, Vector3) =
+            tangent_frame(&normal, &dp_du, &dp_dv).unwrap();
+        assert!((tangent.length() - 1.0).abs() < 1e-12);
+        assert!((bitangent.length() - 1.0).abs() < 1e-12);
+        assert!(dot(&tangent, &normal).abs() < 1e-12);
+        assert!(dot(&bitangent, &normal).abs() < 1e-12);
+        assert!(dot(&tangent, &bitangent).abs() < 1e-12);
+        // The handedness of the coordinates is kept
+        assert!(dot(&bitangent, &dp_dv) > 0.0);
+        assert!(dot(&tangent, &dp_du) > 0.0);
+
+        assert!(tangent_frame(&normal, &Vector3::default(), &dp_dv).is_none());
+    }
+    #[test]
+    fn bump_maps_are_filtered() {
+        /// Steep stripes along u, which average out to a flat grey over any footprint
+        struct Stripes;
+        impl Texture for Stripes {
+            fn value(&self, surface_coords: SurfaceCoordinate, _p: &Point3) -> Color {
+                let height: f64 = (200.0 * surface_coords.u).sin();
+                Color::new(height, height, height)
+            }
+            fn filtered_value(
+                &self,
+                surface_coords: SurfaceCoordinate,
+                p: &Point3,
+                footprint: SurfaceCoordinate,
+            ) -> Color {
+                if footprint.u > 0.1 {
+                    return Color::new(0.5, 0.5, 0.5);
+                }
+                self.value(surface_coords, p)
+            }
+        }
+
+        let material: Arc<NormalMapped> = NormalMapped::bump_map(
+            Lambertian::from_color(Color::white()),
+            Arc::new(Stripes),
+            0.1,
+        );
+        let ray_in: Ray = Ray::new(Point3::new(0.0, 0.0, 1.0), Point3::new(0.0, 0.0, -1.0));
+        let mut record: HitRecord = record_at(Point3::default(), Vector3::new(0.0, 0.0, 1.0), true);
+        record.dp_du = Vector3::new(1.0, 0.0, 0.0);
+        record.dp_dv = Vector3::new(0.0, 1.0, 0.0);
+        record.surface_coords = SurfaceCoordinate { u: 0.3, v: 0.3 };
+
+        // Close up the bumps tilt the normal, far away the surface is flat again
+        let up: Vector3 = Vector3::new(0.0, 0.0, 1.0);
+        let near: Color = material.evaluate(&ray_in, &record, &up);
+        record.footprint = SurfaceCoordinate { u: 0.5, v: 0.5 };
+        let far: Color = material.evaluate(&ray_in, &record, &up);
+
+        assert!((far.x - 1.0 / PI).abs() < 1e-12);
+        assert!(near.x < 0.9 / PI);
+    }
+}
+#[cfg(test)]
+mod aabb {
+    use crate::aabb::AABB;
+    use crate::point3::Point3;
+    use crate::ray::Ray;
+
+    #[test]
+    fn flat_boxes_are_padded_around_their_plane() {
+        let flat: AABB =
+            AABB::from_points(Point3::new(-1.0, -1.0, 0.5), Point3::new(1.0, 1.0, 0.5));
+
+        assert!(flat[2].start < 0.5 && flat[2].end > 0.5);
+        assert!((flat[2].start + flat[2].end - 1.0).abs() < 1e-12);
+        assert_eq!(flat[0], -1.0..1.0);
+
+        // A ray hitting the plane from either side hits the box too
+        for direction in [-1.0, 1.0] {
+            let ray: Ray = Ray::new(
+                Point3::new(0.0, 0.0, 0.5 - direction),
+                Point3::new(0.0, 0.0, direction),
+            );
+            assert!(flat.hit(&ray, &mut (0.001..f64::INFINITY)));
+        }
+        // And one that stops just in front of it does not
+        let ray: Ray = Ray::new(Point3::new(0.0, 0.0, 2.0), Point3::new(0.0, 0.0, -1.0));
+        assert!(!flat.hit(&ray, &mut (0.001..1.49)));
+    }
+}
+
+#[cfg(test)]
+mod cutout {
+    use std::sync::Arc;
+
+    use super::record_at;
+    use crate::bvh::BVHNode;
+    use crate::hittable::parallelogram::Parallelogram;
+    use crate::hittable::quadric::{Quadric, quadric_sphere, y_cylinder};
+    use crate::hittable::sphere::Sphere;
+    use crate::hittable::{HitRecord, Hittable, SurfaceCoordinate};
+    use crate::material::Lambertian;
+    use crate::material::cutout::Cutout;
+    use crate::point3::Point3;
+    use crate::point3::color::Color;
+    use crate::ray::Ray;
+    use crate::texture::{CheckerTexture, SolidColor, Texture};
+
+    #[test]
+    fn rays_go_through_cut_out_parts() {
+        // A quad in front that is solid on the left half and cut out on the right half, and one behind it
+        let holes: Arc<CheckerTexture> =
+            CheckerTexture::from_colors(1.0, Color::black(), Color::white());
+        let front: Parallelogram = Parallelogram::new(
+            Point3::new(-1.0, 0.0, 0.5),
+            Point3::new(2.0, 0.0, 0.0),
+            Point3::new(0.0, 0.9, 0.0),
+            Cutout::new(Lambertian::from_color(Color::white()), holes),
+        );
+        let back: Parallelogram = Parallelogram::new(
+            Point3::new(-1.0, 0.0, -0.5),
+            Point3::new(2.0, 0.0, 0.0),
+            Point3::new(0.0, 0.9, 0.0),
+            Lambertian::from_color(Color::white()),
+        );
+        let world: BVHNode = BVHNode::from_vec(vec![Arc::new(front), Arc::new(back)]);
+
+        let mut record: HitRecord = record_at(Point3::default(), Point3::default(), false);
+        let solid: Ray = Ray::new(Point3::new(-0.5, 0.5, 2.0), Point3::new(0.0, 0.0, -1.0));
+        assert!(world.hit(&solid, &(0.001..f64::INFINITY), &mut record));
+        assert!((record.p.z - 0.5).abs() < 1e-10);
+
+        let cut_out: Ray = Ray::new(Point3::new(0.5, 0.5, 2.0), Point3::new(0.0, 0.0, -1.0));
+        assert!(world.hit(&cut_out, &(0.001..f64::INFINITY), &mut record));
+        assert!((record.p.z + 0.5).abs() < 1e-10);
+    }
+    #[test]
+    fn partial_opacity_stops_some_rays() {
+        let grey: Arc<SolidColor> = SolidColor::new(Color::new(0.25, 0.25, 0.25));
+        let sphere: Sphere = Sphere::new(
+            Point3::default(),
+            1.0,
+            Cutout::new(Lambertian::from_color(Color::white()), grey),
+        );
+        let ray: Ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Point3::new(0.0, 0.0, -1.0));
+
+        // Each of the two sides of the sphere stops a quarter of the rays
+        let trials: u32 = 100_000;
+        let hits: u32 = (0..trials)
+            .filter(|_| {
+                sphere.hit(
+                    &ray,
+                    &(0.001..f64::INFINITY),
+                    &mut record_at(Point3::default(), Point3::default(), false),
+                )
+            })
+            .count() as u32;
+        let expected: f64 = 1.0 - 0.75 * 0.75;
+        assert!((hits as f64 / trials as f64 - expected).abs() < 0.01);
+    }
+    #[test]
+    fn quadrics_can_be_cut_out() {
+        /// Transparent in front of the z = 0 plane and opaque behind it
+        struct BackHalf;
+        impl Texture for BackHalf {
+            fn value(&self, _surface_coords: SurfaceCoordinate, p: &Point3) -> Color {
+                if p.z < 0.0 {
+                    Color::white()
+                } else {
+                    Color::black()
+                }
+            }
+        }
+
+        let material = || Cutout::new(Lambertian::from_color(Color::white()), Arc::new(BackHalf));
+        let quadrics: [Quadric; 2] = [
+            y_cylinder(Point3::default(), 1.0, material()),
+            quadric_sphere(Point3::default(), 1.0, material()),
+        ];
+        let ray: Ray = Ray::new(Point3::new(0.0, 0.5, 5.0), Point3::new(0.0, 0.0, -1.0));
+
+        // The near side is skipped and the far side is hit
+        for quadric in quadrics {
+            let mut record: HitRecord = record_at(Point3::default(), Point3::default(), false);
+            assert!(quadric.hit(&ray, &(0.001..f64::INFINITY), &mut record));
+            assert!(record.p.z < 0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod subsurface {
+    use std::sync::Arc;
+
+    use super::record_at;
+    use crate::hittable::sphere::Sphere;
+    use crate::hittable::{HitRecord, Hittable};
+    use crate::material::subsurface::Subsurface;
+    use crate::material::{Material, ScatteredRayAndAttenuation};
+    use crate::point3::color::Color;
+    use crate::point3::{Point3, Vector3};
+    use crate::ray::Ray;
+
+    #[test]
+    fn walks_without_absorption_keep_the_energy() {
+        // Every channel has a different mean free path, and the walk follows a single wavelength
+        let material: Arc<Subsurface> =
+            Subsurface::new(Color::new(3.0, 4.0, 5.0), Color::black(), 1.4);
+        let sphere: Sphere = Sphere::new(Point3::default(), 1.0, material.clone());
+
+        let walks: u32 = 20_000;
+        let mut total: Color = Color::black();
+        for _ in 0..walks {
+            // Rays either get reflected by the boundary or walk inside until they leave
+            let mut ray: Ray = Ray::new(Point3::new(0.3, 0.2, 3.0), Point3::new(0.0, 0.0, -1.0));
+            let mut throughput: Color = Color::white();
+            let mut record: HitRecord = record_at(Point3::default(), Vector3::default(), false);
+
+            for _ in 0..10_000 {
+                if !sphere.hit(&ray, &(1e-9..f64::INFINITY), &mut record) {
+                    break;
+                }
+
+                let mut sca_att: ScatteredRayAndAttenuation = ScatteredRayAndAttenuation {
+                    scattered_ray: Ray::new(ray.origin, ray.direction),
+                    attenuation: Color::white(),
+                    pdf: 0.0,
+                };
+                assert!(material.scatter(&ray, &record, &mut sca_att));
+                throughput = throughput * sca_att.attenuation;
+
+                // Leaving the sphere is the only scattering with a density
+                if sca_att.pdf > 0.0 {
+                    break;
+                }
+                ray = sca_att.scattered_ray;
+            }
+            total = total + throughput;
+        }
+
+        let average: Color = total / walks as f64;
+        for channel in [average.x, average.y, average.z] {
+            assert!((channel - 1.0).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn from_color_keeps_the_mean_free_path() {
+        // Brighter channels scatter more and absorb less, but all of them travel the same distance between bounces
+        let material: Arc<Subsurface> =
+            Subsurface::from_color(Color::new(0.8, 0.5, 0.2), Color::new(1.0, 1.0, 1.0), 1.0);
+        let scattering: Color = material.scattering;
+        let absorption: Color = material.absorption;
+        assert!(scattering.x > scattering.y && scattering.y > scattering.z);
+        assert!((scattering.x + absorption.x - 1.0).abs() < 1e-10);
+        assert!(absorption.x > 0.0);
+    }
+}
+
+#[cfg(test)]
+mod media {
+    use std::sync::Arc;
+
+    use super::record_at;
+    use crate::hittable::heterogeneous_medium::{DensityField, HeterogeneousMedium};
+    use crate::hittable::sphere::Sphere;
+    use crate::hittable::voxel_grid::{GridPlacement, VoxelGrid};
+    use crate::hittable::{HitRecord, Hittable};
+    use crate::material::{HenyeyGreenstein, Lambertian, Material, ScatteredRayAndAttenuation};
+    use crate::point3::color::Color;
+    use crate::point3::{Point3, Vector3, dot, rotate_y, unit_vector};
+    use crate::ray::Ray;
+    use crate::texture::SolidColor;
+
+    /// Goes from 0 at x = -1 to 2 at x = 1
+    struct Ramp;
+
+    impl DensityField for Ramp {
+        fn density(&self, p: &Point3) -> f64 {
+            (p.x + 1.0).clamp(0.0, 2.0)
+        }
+
+        fn max_density(&self) -> f64 {
+            2.0
+        }
+    }
+
+    #[test]
+    fn delta_tracking_gives_the_transmittance() {
+        let boundary: Arc<Sphere> = Arc::new(Sphere::new(
+            Point3::default(),
+            1.0,
+            Lambertian::from_color(Color::white()),
+        ));
+        let medium: HeterogeneousMedium =
+            HeterogeneousMedium::from_color(boundary, Arc::new(Ramp), Color::white());
+
+        let mut record: HitRecord = record_at(Point3::default(), Vector3::default(), false);
+
+        // Along the x axis the optical depth is the integral of the ramp, 2
+        let ray: Ray = Ray::new(Point3::new(-3.0, 0.0, 0.0), Point3::new(2.0, 0.0, 0.0));
+        let rays: u32 = 40_000;
+        let mut through: u32 = 0;
+        for _ in 0..rays {
+            if !medium.hit(&ray, &(0.001..f64::INFINITY), &mut record) {
+                through += 1;
+            }
+        }
+
+        let transmittance: f64 = through as f64 / rays as f64;
+        assert!((transmittance - (-2.0_f64).exp()).abs() < 0.01);
+    }
+
+    #[test]
+    fn voxel_grids_interpolate_between_centers() {
+        let grid: VoxelGrid = VoxelGrid::new([2, 1, 1], vec![0.0, 2.0]);
+
+        assert!((grid.interpolate(&Point3::new(0.1, 0.5, 0.5))).abs() < 1e-12);
+        assert!((grid.interpolate(&Point3::new(0.5, 0.5, 0.5)) - 1.0).abs() < 1e-12);
+        assert!((grid.interpolate(&Point3::new(0.75, 0.2, 0.9)) - 2.0).abs() < 1e-12);
+        assert_eq!(grid.interpolate(&Point3::new(1.5, 0.5, 0.5)), 0.0);
+        assert_eq!(grid.max_value(), 2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one voxel")]
+    fn voxel_grids_reject_empty_dimensions() {
+        VoxelGrid::new([4, 0, 4], Vec::new());
+    }
+
+    #[test]
+    fn text_and_raw_grids_load_the_same() {
+        let dimensions: [usize; 3] = [3, 2, 2];
+        let values: Vec<f64> = (0..12).map(|i| i as f64 * 0.5).collect();
+
+        let directory: std::path::PathBuf = std::env::temp_dir();
+        let text_path: std::path::PathBuf = directory.join("raytracer_test_grid.txt");
+        let raw_path: std::path::PathBuf = directory.join("raytracer_test_grid.raw");
+
+        let mut text: String = String::from("# a test grid\n3 2 2\n");
+        for value in &values {
+            text.push_str(&format!("{value} "));
+        }
+        std::fs::write(&text_path, text).unwrap();
+
+        let mut raw: Vec<u8> = Vec::new();
+        for dimension in dimensions {
+            raw.extend_from_slice(&(dimension as u32).to_le_bytes());
+        }
+        for value in &values {
+            raw.extend_from_slice(&(*value as f32).to_le_bytes());
+        }
+        std::fs::write(&raw_path, raw).unwrap();
+
+        let from_text: VoxelGrid = VoxelGrid::load_text(text_path.to_str().unwrap());
+        let from_raw: VoxelGrid = VoxelGrid::load_raw(raw_path.to_str().unwrap());
+
+        assert_eq!(from_text.dimensions, dimensions);
+        assert_eq!(from_raw.dimensions, dimensions);
+        for local in [
+            Point3::new(0.2, 0.3, 0.4),
+            Point3::new(0.9, 0.6, 0.1),
+            Point3::new(0.5, 0.5, 0.5),
+        ] {
+            assert!((from_text.interpolate(&local) - from_raw.interpolate(&local)).abs() < 1e-6);
+        }
+        // The last voxel, at the center of its corner
+        assert!((from_text.interpolate(&Point3::new(5.0 / 6.0, 0.75, 0.75)) - 5.5).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a number: \"0.5x\"")]
+    fn text_grids_name_the_value_they_cannot_read() {
+        let path: std::path::PathBuf = std::env::temp_dir().join("raytracer_test_bad_grid.txt");
+        std::fs::write(&path, "1 1 2\n0.25 0.5x\n").unwrap();
+
+        VoxelGrid::load_text(path.to_str().unwrap());
+    }
+
+    #[test]
+    fn grid_placement_matches_its_boundary() {
+        let placement: GridPlacement = GridPlacement::new(
+            Point3::new(1.0, 0.0, -2.0),
+            Vector3::new(2.0, 1.0, 3.0),
+            30.0,
+        );
+        let boundary: Arc<dyn Hittable> = placement.boundary();
+
+        let mut record: HitRecord = record_at(Point3::default(), Vector3::default(), false);
+
+        // The center of the box, turned from object space into world space like RotateY does
+        let radians: f64 = 30.0_f64.to_radians();
+        let center: Point3 = rotate_y(&Point3::new(1.0, 0.5, 1.5), radians.cos(), -radians.sin())
+            + Point3::new(1.0, 0.0, -2.0);
+        assert!((placement.to_local(&center) - Point3::new(0.5, 0.5, 0.5)).length() < 1e-12);
+
+        // Rays towards it hit the sides of the grid, where a local coordinate is 0 or 1
+        for origin in [
+            Point3::new(10.0, 0.5, 0.0),
+            Point3::new(-10.0, 0.7, 1.0),
+            Point3::new(0.0, 10.0, 0.0),
+        ] {
+            let ray: Ray = Ray::new(origin, center - origin);
+            assert!(boundary.hit(&ray, &(0.001..f64::INFINITY), &mut record));
+
+            let local: Point3 = placement.to_local(&record.p);
+            let on_side = |c: f64| -> bool { c.abs() < 1e-9 || (c - 1.0).abs() < 1e-9 };
+            assert!(on_side(local.x) || on_side(local.y) || on_side(local.z));
+            for c in [local.x, local.y, local.z] {
+                assert!((-1e-9..=1.0 + 1e-9).contains(&c));
+            }
+        }
+    }
+
+    #[test]
+    fn henyey_greenstein_average_cosine_is_g() {
+        let record: HitRecord = record_at(Point3::default(), Vector3::new(1.0, 0.0, 0.0), true);
+        let ray_in: Ray = Ray::new(Point3::new(0.0, 0.0, 1.0), Point3::new(0.3, -0.2, -1.0));
+        let forward: Vector3 = unit_vector(ray_in.direction);
+
+        let cases: [(Arc<HenyeyGreenstein>, f64); 2] = [
+            (
+                HenyeyGreenstein::new(SolidColor::new(Color::white()), 0.6),
+                0.6,
+            ),
+            (
+                HenyeyGreenstein::two_lobes(SolidColor::new(Color::white()), 0.8, -0.4, 0.25),
+                0.75 * 0.8 - 0.25 * 0.4,
+            ),
+        ];
+
+        for (material, expected) in cases {
+            let samples: u32 = 100_000;
+            let mut average: f64 = 0.0;
+            for _ in 0..samples {
+                let mut sca_att: ScatteredRayAndAttenuation = ScatteredRayAndAttenuation {
+                    scattered_ray: Ray::new(Point3::default(), forward),
+                    attenuation: Color::white(),
+                    pdf: 0.0,
+                };
+                assert!(material.scatter(&ray_in, &record, &mut sca_att));
+
+                let direction: Vector3 = sca_att.scattered_ray.direction;
+                let pdf: f64 = material.scattering_pdf(&ray_in, &record, &direction);
+                assert!((sca_att.pdf - pdf).abs() < 1e-9 * pdf.max(1.0));
+
+                average += dot(&forward, &unit_vector(direction)) / samples as f64;
+            }
+            assert!((average - expected).abs() < 0.01);
+        }
+    }
+}
+#[cfg(test)]
+mod nested_media {
+    use std::sync::Arc;
+
+    use super::record_at;
+    use crate::camera::ray_color;
+    use crate::hittable::constant_medium::ConstantMedium;
+    use crate::hittable::hittable_list::{HittableList, HittableSlice};
+    use crate::hittable::parallelogram::Parallelogram;
+    use crate::hittable::sphere::Sphere;
+    use crate::hittable::{HitRecord, Hittable};
+    use crate::light::Lighting;
+    use crate::light::delta::PointLight;
+    use crate::material::rough_dielectric::RoughDielectric;
+    use crate::material::{
+        Dielectric, Lambertian, Material, ScatteredRayAndAttenuation, dielectric,
+    };
+    use crate::point3::color::Color;
+    use crate::point3::{Point3, Vector3, reflect, refract, unit_vector};
+    use crate::ray::{MediumStack, Ray, next_medium_id};
+
+    fn scatter(material: &Dielectric, ray_in: &Ray) -> ScatteredRayAndAttenuation {
+        scatter_at(
+            material,
+            ray_in,
+            &record_at(Point3::default(), Vector3::new(0.0, 1.0, 0.0), true),
+        )
+    }
+
+    fn scatter_at(
+        material: &dyn Material,
+        ray_in: &Ray,
+        record: &HitRecord,
+    ) -> ScatteredRayAndAttenuation {
+        let mut sca_att: ScatteredRayAndAttenuation = ScatteredRayAndAttenuation {
+            scattered_ray: Ray::new(Point3::default(), Vector3::new(1.0, 0.0, 0.0)),
+            attenuation: Color::white(),
+            pdf: 0.0,
+        };
+        assert!(material.scatter(ray_in, record, &mut sca_att));
+        sca_att
+    }
+
+    #[test]
+    fn ice_in_water_refracts_with_both_indices() {
+        let water: Arc<Dielectric> = dielectric(1.33);
+        let ice: Arc<Dielectric> = dielectric(1.31);
+        let mut media: MediumStack = MediumStack::default();
+        media.push(water.medium_entry(water.refraction_index));
+
+        let direction: Vector3 = unit_vector(Vector3::new(1.0, -1.0, 0.0));
+        let ray_in: Ray =
+            Ray::new(Point3::new(-1.0, 1.0, 0.0), direction).with_media(Some(Arc::new(media)));
+        let normal: Vector3 = Vector3::new(0.0, 1.0, 0.0);
+        let refracted: Vector3 = refract(direction, normal, 1.33 / 1.31);
+
+        for _ in 0..100 {
+            let sca_att: ScatteredRayAndAttenuation = scatter(&ice, &ray_in);
+            let scattered: &Ray = &sca_att.scattered_ray;
+            let media_after: MediumStack = *scattered.media.clone().unwrap();
+
+            if (scattered.direction - reflect(direction, normal)).length() < 1e-9 {
+                assert_eq!(
+                    media_after.current().unwrap().id,
+                    media.current().unwrap().id
+                );
+            } else {
+                assert!((scattered.direction - refracted).length() < 1e-9);
+                assert_eq!(media_after.current().unwrap().refraction_index, 1.31);
+                assert!(media_after.contains(media.current().unwrap().id));
+            }
+        }
+    }
+
+    #[test]
+    fn surfaces_inside_a_higher_priority_medium_are_ignored() {
+        let glass: Arc<Dielectric> = Dielectric::prioritized(1.5, Color::white(), 1.0, 2);
+        let water: Arc<Dielectric> = Dielectric::prioritized(1.33, Color::white(), 1.0, 1);
+        let mut media: MediumStack = MediumStack::default();
+        media.push(glass.medium_entry(glass.refraction_index));
+
+        let direction: Vector3 = unit_vector(Vector3::new(1.0, -1.0, 0.0));
+        let ray_in: Ray =
+            Ray::new(Point3::new(-1.0, 1.0, 0.0), direction).with_media(Some(Arc::new(media)));
+
+        for _ in 0..100 {
+            let sca_att: ScatteredRayAndAttenuation = scatter(&water, &ray_in);
+            let media_after: MediumStack = *sca_att.scattered_ray.media.unwrap();
+
+            assert!((sca_att.scattered_ray.direction - direction).length() < 1e-12);
+            assert_eq!(media_after.current().unwrap().refraction_index, 1.5);
+
+            // Once the ray leaves the glass it is in the water
+            let mut outside_glass: MediumStack = media_after;
+            outside_glass.remove(media.current().unwrap().id);
+            assert_eq!(outside_glass.current().unwrap().refraction_index, 1.33);
+        }
+    }
+
+    #[test]
+    fn fog_works_from_inside_and_with_several_parts() {
+        let sphere = |x: f64| -> Sphere {
+            Sphere::new(
+                Point3::new(x, 0.0, 0.0),
+                1.0,
+                Lambertian::from_color(Color::white()),
+            )
+        };
+        let mut parts: HittableList = HittableList::default();
+        parts.add(sphere(0.0));
+        parts.add(sphere(3.0));
+        let boundary: Arc<HittableSlice> = Arc::new(parts.to_hittable_slice());
+        let fog: ConstantMedium = ConstantMedium::from_color(boundary, 0.5, Color::white());
+
+        let mut record: HitRecord = record_at(Point3::default(), Vector3::new(0.0, 1.0, 0.0), true);
+        let transmittance = |ray: &Ray, record: &mut HitRecord| -> f64 {
+            let rays: u32 = 40_000;
+            let through: usize = (0..rays)
+                .filter(|_| !fog.hit(ray, &(0.001..f64::INFINITY), record))
+                .count();
+            through as f64 / rays as f64
+        };
+
+        // From the center of the first sphere: its radius, then all of the second one
+        let from_inside: Ray = Ray::new(Point3::default(), Vector3::new(1.0, 0.0, 0.0));
+        let expected: f64 = (-0.5_f64 * 3.0).exp();
+        assert!((transmittance(&from_inside, &mut record) - expected).abs() < 0.01);
+
+        // From outside, through both of them
+        let from_outside: Ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let expected: f64 = (-0.5_f64 * 4.0).exp();
+        assert!((transmittance(&from_outside, &mut record) - expected).abs() < 0.01);
+    }
+
+    #[test]
+    fn diffuse_surfaces_inside_a_medium_are_tinted() {
+        // A floor lit by a point light, seen from 1 unit above it. With a black background the only light is the
+        // one of the point light, so the result is the same every time
+        let floor: Parallelogram = Parallelogram::new(
+            Point3::new(-10.0, 0.0, -10.0),
+            Vector3::new(20.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 20.0),
+            Lambertian::from_color(Color::white()),
+        );
+        let mut lighting: Lighting = Lighting::from_background_color(Color::black());
+        lighting.lights.push(PointLight::new(
+            Point3::new(0.0, 2.0, 0.0),
+            Color::white(),
+            100.0,
+        ));
+
+        let color: Color = Color::new(0.5, 0.8, 1.0);
+        let water: Arc<Dielectric> = Dielectric::tinted(1.33, color, 1.0);
+        let mut media: MediumStack = MediumStack::default();
+        media.push(water.medium_entry(water.refraction_index));
+
+        let down: Ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        let in_air: Color = ray_color(&down, 5, &floor, &lighting, None);
+        let in_water: Color = ray_color(
+            &down.with_media(Some(Arc::new(media))),
+            5,
+            &floor,
+            &lighting,
+            None,
+        );
+
+        // 1 unit from the floor to the eye, and 2 from the light to the floor
+        let expected: Color = color * color * color;
+        assert!(in_air.x > 0.0);
+        assert!((in_water.x / in_air.x - expected.x).abs() < 1e-9);
+        assert!((in_water.y / in_air.y - expected.y).abs() < 1e-9);
+        assert!((in_water.z / in_air.z - expected.z).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rough_ice_in_water_refracts_and_absorbs_once() {
+        let water: Arc<Dielectric> = Dielectric::tinted(1.33, Color::new(0.5, 0.8, 1.0), 1.0);
+        let ice: Arc<RoughDielectric> =
+            RoughDielectric::tinted(1.31, 0.0, Color::new(0.9, 0.95, 1.0), 1.0);
+        let mut in_water: MediumStack = MediumStack::default();
+        in_water.push(water.medium_entry(water.refraction_index));
+
+        // Going into the ice from the water bends the ray with the ratio of both indices
+        let direction: Vector3 = unit_vector(Vector3::new(1.0, -1.0, 0.0));
+        let normal: Vector3 = Vector3::new(0.0, 1.0, 0.0);
+        let ray_in: Ray =
+            Ray::new(Point3::new(-1.0, 1.0, 0.0), direction).with_media(Some(Arc::new(in_water)));
+        let refracted: Vector3 = refract(direction, normal, 1.33 / 1.31);
+        let mut entered: bool = false;
+        for _ in 0..100 {
+            let sca_att: ScatteredRayAndAttenuation =
+                scatter_at(&*ice, &ray_in, &record_at(Point3::default(), normal, true));
+            let media_after: MediumStack = *sca_att.scattered_ray.media.clone().unwrap();
+
+            if (sca_att.scattered_ray.direction - refracted).length() < 1e-9 {
+                entered = true;
+                assert_eq!(media_after.current().unwrap().id, ice.medium_id);
+                assert!(media_after.contains(water.medium_id));
+            } else {
+                assert_eq!(media_after.current().unwrap().id, water.medium_id);
+            }
+            assert_eq!(sca_att.attenuation, Color::white());
+        }
+        assert!(entered);
+
+        // Leaving it the ray is back in the water. The light absorbed inside is left to the camera, which knows
+        // the ray was in the ice
+        let mut in_ice: MediumStack = in_water;
+        in_ice.push(ice.medium_entry());
+        let up: Vector3 = Vector3::new(0.0, 1.0, 0.0);
+        let ray_in: Ray =
+            Ray::new(Point3::new(0.0, -1.0, 0.0), up).with_media(Some(Arc::new(in_ice)));
+        let sca_att: ScatteredRayAndAttenuation =
+            scatter_at(&*ice, &ray_in, &record_at(Point3::default(), -up, false));
+        let media_after: MediumStack = *sca_att.scattered_ray.media.unwrap();
+
+        assert!((sca_att.scattered_ray.direction - up).length() < 1e-9);
+        assert_eq!(media_after.current().unwrap().id, water.medium_id);
+        assert!(!media_after.contains(ice.medium_id));
+        assert_eq!(sca_att.attenuation, Color::white());
+    }
+
+    #[test]
+    fn media_are_identified_by_their_material() {
+        let water: Dielectric = Dielectric {
+            refraction_index: 1.33,
+            absorption: Color::black(),
+            dispersion: None,
+            priority: 0,
+            medium_id: next_medium_id(),
+        };
+        let id: usize = water.medium_entry(1.33).id;
+
+        // Moving the material into the scene keeps it the same medium, and an equal one is another medium
+        let water: Arc<Dielectric> = Arc::new(water);
+        assert_eq!(water.medium_entry(1.33).id, id);
+        assert_ne!(dielectric(1.33).medium_entry(1.33).id, id);
+    }
+}
+#[cfg(test)]
+mod textures {
+    use std::sync::Arc;
+
+    use crate::hittable::SurfaceCoordinate;
+    use crate::point3::color::Color;
+    use crate::point3::{Point3, Vector3};
+    use crate::texture::{
+        CheckerTexture, ColorSpace, ImageTexture, Texture, TextureFilter, TransformedTexture,
+        WrapMode, load_linear_image,
+    };
+    use image::{ImageBuffer, Rgb, Rgb32FImage};
+
+    fn uv(u: f64, v: f64) -> SurfaceCoordinate {
+        SurfaceCoordinate { u, v }
+    }
+
+    #[test]
+    fn surface_checkers_follow_the_coordinates() {
+        let checker: Arc<CheckerTexture> =
+            CheckerTexture::surface_from_colors(4.0, Color::white(), Color::black());
+        let p: Point3 = Point3::new(0.3, 0.7, 0.1);
+
+        assert_eq!(checker.value(uv(0.1, 0.1), &p).x, 1.0);
+        assert_eq!(checker.value(uv(0.3, 0.1), &p).x, 0.0);
+        assert_eq!(checker.value(uv(0.3, 0.3), &p).x, 1.0);
+        // The point does not matter
+        assert_eq!(
+            checker.value(uv(0.3, 0.1), &Point3::new(5.5, -2.5, 3.0)).x,
+            0.0
+        );
+    }
+
+    #[test]
+    fn transforms_move_the_coordinates() {
+        let checker: Arc<CheckerTexture> =
+            CheckerTexture::surface_from_colors(2.0, Color::white(), Color::black());
+
+        let close = |a: SurfaceCoordinate, b: SurfaceCoordinate| -> bool {
+            (a.u - b.u).abs() < 1e-12 && (a.v - b.v).abs() < 1e-12
+        };
+
+        let rotated: TransformedTexture =
+            TransformedTexture::new(checker.clone()).with_rotation(90.0);
+        assert!(close(rotated.transform(uv(1.0, 0.5)), uv(0.5, 1.0)));
+
+        let scaled: TransformedTexture = TransformedTexture::new(checker.clone())
+            .with_scale(2.0, 1.0)
+            .with_offset(0.25, 0.0);
+        assert!(close(scaled.transform(uv(0.75, 0.2)), uv(1.25, 0.2)));
+
+        let tiled: TransformedTexture =
+            TransformedTexture::new(checker.clone()).with_tiling(3.0, 2.0);
+        assert!(close(tiled.transform(uv(0.5, 0.75)), uv(0.5, 0.5)));
+        // Each tile has the whole checker
+        assert_eq!(tiled.value(uv(0.1, 0.1), &Point3::default()).x, 1.0);
+        assert_eq!(tiled.value(uv(0.4, 0.1), &Point3::default()).x, 1.0);
+
+        let solid: TransformedTexture = TransformedTexture::new(CheckerTexture::from_colors(
+            1.0,
+            Color::white(),
+            Color::black(),
+        ))
+        .with_point_transform(1.0, Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(
+            solid.value(uv(0.0, 0.0), &Point3::new(0.5, 0.5, 0.5)).x,
+            0.0
+        );
+    }
+
+    #[test]
+    fn image_wrap_modes() {
+        // Red on the left, blue on the right
+        let image: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_fn(2, 1, |x, _| {
+            if x == 0 {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 255])
+            }
+        });
+        let color_at = |wrap_mode: WrapMode, u: f64| -> Color {
+            ImageTexture::from_image(&image, TextureFilter::Nearest, wrap_mode, ColorSpace::Srgb)
+                .value(uv(u, 0.5), &Point3::default())
+        };
+
+        assert_eq!(color_at(WrapMode::Repeat, 1.25).x, 1.0);
+        assert_eq!(color_at(WrapMode::Mirror, 1.25).z, 1.0);
+        assert_eq!(color_at(WrapMode::Mirror, 1.75).x, 1.0);
+        assert_eq!(color_at(WrapMode::Clamp, 1.25).z, 1.0);
+        assert_eq!(color_at(WrapMode::Clamp, -3.0).x, 1.0);
+
+        // Halfway between the centers of the pixels
+        let bilinear: Color = ImageTexture::from_image(
+            &image,
+            TextureFilter::Bilinear,
+            WrapMode::Clamp,
+            ColorSpace::Linear,
+        )
+        .value(uv(0.5, 0.5), &Point3::default());
+        assert!((bilinear.x - 0.5).abs() < 1e-6 && (bilinear.z - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn mipmaps_average_far_away_pixels() {
+        // Single pixel black and white checker
+        let image: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_fn(8, 4, |x, y| {
+            let value: u8 = if (x + y) % 2 == 0 { 255 } else { 0 };
+            Rgb([value, value, value])
+        });
+        let texture: ImageTexture = ImageTexture::from_image(
+            &image,
+            TextureFilter::Trilinear,
+            WrapMode::Repeat,
+            ColorSpace::Linear,
+        );
+        assert_eq!(texture.level_count(), 4);
+
+        // Up close the pixels are there, far away they become grey
+        let center_of_pixel: SurfaceCoordinate = uv(0.5 / 8.0, 1.0 - 0.5 / 4.0);
+        let close: Color =
+            texture.filtered_value(center_of_pixel, &Point3::default(), uv(0.0, 0.0));
+        assert!((close.x - 1.0).abs() < 1e-6);
+
+        for footprint in [uv(0.25, 0.0), uv(0.0, 0.5), uv(10.0, 10.0)] {
+            let far: Color = texture.filtered_value(center_of_pixel, &Point3::default(), footprint);
+            assert!((far.x - 0.5).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn mipmaps_of_odd_sizes_keep_every_pixel() {
+        // Only the last column is white, which halving 5 pixels to 2 with a 2x2 box would skip
+        let image: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_fn(5, 3, |x, _| {
+            let value: u8 = if x == 4 { 255 } else { 0 };
+            Rgb([value, value, value])
+        });
+        let texture: ImageTexture = ImageTexture::from_image(
+            &image,
+            TextureFilter::Trilinear,
+            WrapMode::Repeat,
+            ColorSpace::Linear,
+        );
+        assert_eq!(texture.level_count(), 3);
+
+        // The last level is the average of the whole image
+        let far: Color = texture.filtered_value(uv(0.5, 0.5), &Point3::default(), uv(10.0, 10.0));
+        assert!((far.x - 0.2).abs() < 1e-6);
+
+        // The one before it has 2x1 pixels. The right one covers columns 2.5 to 5
+        let right: Color = texture.filtered_value(uv(0.75, 0.5), &Point3::default(), uv(0.4, 0.0));
+        assert!((right.x - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one pixel")]
+    fn image_textures_reject_empty_images() {
+        let image: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(0, 4);
+        ImageTexture::from_image(
+            &image,
+            TextureFilter::Nearest,
+            WrapMode::Repeat,
+            ColorSpace::Linear,
+        );
+    }
+
+    #[test]
+    fn color_images_are_decoded_and_data_is_not() {
+        let image: ImageBuffer<Rgb<u8>, Vec<u8>> =
+            ImageBuffer::from_pixel(1, 1, Rgb([128, 128, 128]));
+        let value = |color_space: ColorSpace| -> f64 {
+            ImageTexture::from_image(&image, TextureFilter::Nearest, WrapMode::Clamp, color_space)
+                .value(uv(0.5, 0.5), &Point3::default())
+                .x
+        };
+
+        assert!((value(ColorSpace::Srgb) - 0.2158605).abs() < 1e-5);
+        assert!((value(ColorSpace::Linear) - 128.0 / 255.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn float_images_keep_their_values() {
+        let directory: std::path::PathBuf = std::env::temp_dir();
+        let bright: Rgb32FImage = ImageBuffer::from_pixel(2, 2, Rgb([4.0, 0.5, 0.25]));
+
+        for extension in ["hdr", "exr"] {
+            let path: std::path::PathBuf =
+                directory.join(format!("raytracer_test_texture.{}", extension));
+            bright.save(&path).unwrap();
+
+            // Float formats are linear, whatever the color space says
+            let texture: Arc<dyn Texture> = ImageTexture::with_sampling_or_fallback(
+                path.to_str().unwrap(),
+                TextureFilter::Bilinear,
+                WrapMode::Repeat,
+                ColorSpace::Srgb,
+            );
+            let color: Color = texture.value(uv(0.3, 0.6), &Point3::default());
+            assert!((color.x - 4.0).abs() < 0.05);
+            assert!((color.y - 0.5).abs() < 0.01);
+
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        let path: std::path::PathBuf = directory.join("raytracer_test_texture.png");
+        ImageBuffer::<Rgb<u8>, Vec<u8>>::from_pixel(1, 1, Rgb([128, 64, 255]))
+            .save(&path)
+            .unwrap();
+        let decoded: Rgb32FImage =
+            load_linear_image(path.to_str().unwrap(), ColorSpace::Srgb).unwrap();
+        let data: Rgb32FImage =
+            load_linear_image(path.to_str().unwrap(), ColorSpace::Linear).unwrap();
+        assert!((decoded.get_pixel(0, 0).0[0] - 0.2158605).abs() < 1e-5);
+        assert!((data.get_pixel(0, 0).0[1] - 64.0 / 255.0).abs() < 1e-6);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+#[cfg(test)]
+mod perlin {
+    use crate::perlin::{Fbm, PerlinNoise};
+    use crate::point3::Point3;
+
+    #[test]
+    fn tables_are_shuffled_permutations() {
+        let noise: PerlinNoise = PerlinNoise::new(7);
+        let identity: Vec<u32> = (0..256).collect();
+
+        for table in [&noise.x_perm, &noise.y_perm, &noise.z_perm] {
+            let mut sorted: Vec<u32> = table.to_vec();
+            sorted.sort();
+            assert_eq!(sorted, identity);
+            assert_ne!(table.to_vec(), identity);
+        }
+        assert_ne!(noise.x_perm, noise.y_perm);
+    }
+
+    #[test]
+    fn seeds_give_the_same_noise() {
+        let p: Point3 = Point3::new(1.3, -2.7, 0.4);
+        let a: PerlinNoise = PerlinNoise::new(3);
+        let b: PerlinNoise = PerlinNoise::new(3);
+        let c: PerlinNoise = PerlinNoise::new(4);
+
+        assert_eq!(a.x_perm, b.x_perm);
+        assert_eq!(a.noise(&p), b.noise(&p));
+        assert_ne!(a.noise(&p), c.noise(&p));
+    }
+
+    #[test]
+    fn fbm_adds_scaled_octaves() {
+        let noise: PerlinNoise = PerlinNoise::new(11);
+        let p: Point3 = Point3::new(0.37, 1.61, -4.2);
+
+        assert_eq!(noise.fbm(&p, &Fbm::new(1)), noise.noise(&p));
+
+        let fbm: Fbm = Fbm::new(2).with_lacunarity(3.0).with_gain(0.25);
+        let expected: f64 = noise.noise(&p) + 0.25 * noise.noise(&(3.0 * p));
+        assert!((noise.fbm(&p, &fbm) - expected).abs() < 1e-12);
+
+        // The book's turbulence is the same as before
+        assert_eq!(noise.turbulence(&p, 7), noise.fbm(&p, &Fbm::new(7)).abs());
+    }
+}
+
+#[cfg(test)]
+mod procedural {
+    use std::sync::Arc;
+
+    use crate::noise::{SimplexNoise, WorleyDistance, WorleyNoise};
+    use crate::point3::Point3;
+    use crate::point3::color::Color;
+    use crate::texture::procedural::{ColorRamp, WoodTexture};
+
+    #[test]
+    fn ramp_blends_sorted_stops() {
+        let red: Color = Color::new(1.0, 0.0, 0.0);
+        let blue: Color = Color::new(0.0, 0.0, 1.0);
+        let ramp: ColorRamp = ColorRamp::new(vec![(0.8, blue), (0.2, red)]);
+
+        assert_eq!(ramp.stops()[0].0, 0.2);
+        assert!((ramp.at(0.5) - Color::new(0.5, 0.0, 0.5)).length() < 1e-12);
+        // Clamped to the first and last stops
+        assert_eq!(ramp.at(-1.0), red);
+        assert_eq!(ramp.at(0.1), red);
+        assert_eq!(ramp.at(0.9), blue);
+        assert_eq!(ramp.at(3.0), blue);
+    }
+
+    #[test]
+    fn worley_distances_are_ordered() {
+        let noise: WorleyNoise = WorleyNoise::new(9);
+        let other: WorleyNoise = WorleyNoise::new(10);
+
+        for i in 0..100 {
+            let p: Point3 = Point3::new(0.37 * i as f64, -1.3 * i as f64, 0.11 * i as f64 - 4.0);
+            let (closest, second_closest): (f64, f64) = noise.distances(&p);
+
+            assert!(closest <= second_closest);
+            // There is a feature point in the cell of p
+            assert!(closest <= 3.0_f64.sqrt());
+            assert_eq!(
+                noise.noise(&p, WorleyDistance::Border),
+                second_closest - closest
+            );
+            assert_eq!(WorleyNoise::new(9).distances(&p), (closest, second_closest));
+        }
+        let p: Point3 = Point3::new(0.5, 0.5, 0.5);
+        assert_ne!(noise.distances(&p), other.distances(&p));
+    }
+
+    #[test]
+    fn simplex_is_zero_on_the_lattice_and_bounded() {
+        let noise: SimplexNoise = SimplexNoise::new(5);
+
+        // Corners of the tetrahedra: the origin, and (1, 1, 1) moved back by the skew of the grid
+        assert_eq!(noise.noise(&Point3::new(0.0, 0.0, 0.0)), 0.0);
+        assert_eq!(noise.noise(&Point3::new(0.5, 0.5, 0.5)), 0.0);
+        for i in 0..1000 {
+            let p: Point3 =
+                Point3::new(0.173 * i as f64, 0.311 * i as f64 - 50.0, -0.057 * i as f64);
+            let value: f64 = noise.noise(&p);
+
+            assert!((-1.0..=1.0).contains(&value));
+            assert_eq!(SimplexNoise::new(5).noise(&p), value);
+        }
+    }
+
+    #[test]
+    fn wood_rings_repeat() {
+        let wood: Arc<WoodTexture> = WoodTexture::new(1, 4.0, 0.0, 1.0, ColorRamp::grey());
+
+        // Without distortion, a quarter of a unit further from the axis is a whole ring further, at any height
+        let a: f64 = wood.amount(&Point3::new(0.3, 2.0, 0.0));
+        let b: f64 = wood.amount(&Point3::new(0.0, -1.0, 0.55));
+        assert!((a - 0.2).abs() < 1e-9);
+        assert!((a - b).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,727 @@
+use std::sync::Arc;
+
+use image::error::{ImageError, LimitError, LimitErrorKind};
+use image::{DynamicImage, ImageBuffer, ImageResult, Rgb, Rgb32FImage, Rgba32FImage, open};
+
+use crate::{
+    hittable::{HitRecord, SurfaceCoordinate},
+    perlin::{Fbm, PerlinNoise},
+    point3::{
+        Point3, Vector3,
+        color::{Color, srgb_to_linear},
+    },
+};
+
+pub trait Texture: Sync + Send {
+    fn value(&self, surface_coords: SurfaceCoordinate, p: &Point3) -> Color;
+
+    /// The average of the texture over an area around the point, as big as footprint along each of the surface
+    /// coordinates. Only textures that can alias, like images, need to filter
+    fn filtered_value(
+        &self,
+        surface_coords: SurfaceCoordinate,
+        p: &Point3,
+        _footprint: SurfaceCoordinate,
+    ) -> Color {
+        self.value(surface_coords, p)
+    }
+
+    /// The filtered value at a hit
+    fn value_at(&self, record: &HitRecord) -> Color {
+        self.filtered_value(record.surface_coords, &record.p, record.footprint)
+    }
+}
+
+pub struct SolidColor {
+    albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> Arc<SolidColor> {
+        Arc::new(SolidColor { albedo: color })
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _surface_coords: SurfaceCoordinate, _p: &Point3) -> Point3 {
+        self.albedo
+    }
+}
+
+/// Where a texture is looked up
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextureMapping {
+    /// The point in the scene, so the texture is solid and objects look carved out of it
+    World,
+    /// The surface coordinates (u, v) of the hit, so the texture is wrapped around the object
+    Surface,
+}
+
+pub struct CheckerTexture {
+    pub inverse_scale: f64,
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    pub mapping: TextureMapping,
+}
+
+impl CheckerTexture {
+    pub fn from_pointers(
+        scale: f64,
+        even: Arc<dyn Texture>,
+        odd: Arc<dyn Texture>,
+    ) -> Arc<CheckerTexture> {
+        Arc::new(CheckerTexture {
+            inverse_scale: 1.0 / scale,
+            even,
+            odd,
+            mapping: TextureMapping::World,
+        })
+    }
+
+    pub fn from_colors(scale: f64, even: Point3, odd: Point3) -> Arc<CheckerTexture> {
+        Arc::new(CheckerTexture {
+            inverse_scale: 1.0 / scale,
+            even: SolidColor::new(even),
+            odd: SolidColor::new(odd),
+            mapping: TextureMapping::World,
+        })
+    }
+
+    /// A checker wrapped around the surface, with squares along both u and v. Spheres need an even number of
+    /// them to not have a seam
+    pub fn surface_from_pointers(
+        squares: f64,
+        even: Arc<dyn Texture>,
+        odd: Arc<dyn Texture>,
+    ) -> Arc<CheckerTexture> {
+        Arc::new(CheckerTexture {
+            inverse_scale: squares,
+            even,
+            odd,
+            mapping: TextureMapping::Surface,
+        })
+    }
+
+    pub fn surface_from_colors(squares: f64, even: Point3, odd: Point3) -> Arc<CheckerTexture> {
+        CheckerTexture::surface_from_pointers(squares, SolidColor::new(even), SolidColor::new(odd))
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, surface_coords: SurfaceCoordinate, p: &Point3) -> Point3 {
+        self.filtered_value(surface_coords, p, SurfaceCoordinate { u: 0.0, v: 0.0 })
+    }
+
+    fn filtered_value(
+        &self,
+        surface_coords: SurfaceCoordinate,
+        p: &Point3,
+        footprint: SurfaceCoordinate,
+    ) -> Color {
+        let sum: i64 = match self.mapping {
+            TextureMapping::World => {
+                let x_integer: i64 = (self.inverse_scale * p.x).floor() as i64;
+                let y_integer: i64 = (self.inverse_scale * p.y).floor() as i64;
+                let z_integer: i64 = (self.inverse_scale * p.z).floor() as i64;
+                x_integer + y_integer + z_integer
+            }
+            TextureMapping::Surface => {
+                let u_integer: i64 = (self.inverse_scale * surface_coords.u).floor() as i64;
+                let v_integer: i64 = (self.inverse_scale * surface_coords.v).floor() as i64;
+                u_integer + v_integer
+            }
+        };
+
+        let is_even: bool = sum.rem_euclid(2) == 0;
+
+        if is_even {
+            self.even.filtered_value(surface_coords, p, footprint)
+        } else {
+            self.odd.filtered_value(surface_coords, p, footprint)
+        }
+    }
+}
+
+/// Another texture with its coordinates moved: the surface coordinates are scaled and rotated around the center of
+/// the texture (0.5, 0.5), then moved and tiled, and the points of solid textures are scaled and moved. Build it
+/// with new() and the with_ methods, which can be chained
+pub struct TransformedTexture {
+    pub texture: Arc<dyn Texture>,
+    pub scale: SurfaceCoordinate,
+    /// Counterclockwise, in degrees
+    pub rotation: f64,
+    pub offset: SurfaceCoordinate,
+    /// Number of times the texture is repeated along u and v, wrapping the coordinates back into 0..1.
+    /// None to leave them as they are
+    pub tiles: Option<SurfaceCoordinate>,
+    /// For solid textures, the point p is looked up at p * point_scale + point_offset
+    pub point_scale: f64,
+    pub point_offset: Vector3,
+}
+
+impl TransformedTexture {
+    /// The texture as it is, to add transforms to
+    pub fn new(texture: Arc<dyn Texture>) -> TransformedTexture {
+        TransformedTexture {
+            texture,
+            scale: SurfaceCoordinate { u: 1.0, v: 1.0 },
+            rotation: 0.0,
+            offset: SurfaceCoordinate { u: 0.0, v: 0.0 },
+            tiles: None,
+            point_scale: 1.0,
+            point_offset: Vector3::default(),
+        }
+    }
+
+    pub fn with_scale(mut self, u: f64, v: f64) -> TransformedTexture {
+        self.scale = SurfaceCoordinate { u, v };
+        self
+    }
+
+    pub fn with_rotation(mut self, angle_in_degrees: f64) -> TransformedTexture {
+        self.rotation = angle_in_degrees;
+        self
+    }
+
+    pub fn with_offset(mut self, u: f64, v: f64) -> TransformedTexture {
+        self.offset = SurfaceCoordinate { u, v };
+        self
+    }
+
+    pub fn with_tiling(mut self, u: f64, v: f64) -> TransformedTexture {
+        self.tiles = Some(SurfaceCoordinate { u, v });
+        self
+    }
+
+    pub fn with_point_transform(mut self, scale: f64, offset: Vector3) -> TransformedTexture {
+        self.point_scale = scale;
+        self.point_offset = offset;
+        self
+    }
+
+    /// Where the texture is looked up for the surface coordinates of a hit
+    pub fn transform(&self, surface_coords: SurfaceCoordinate) -> SurfaceCoordinate {
+        let (sin_theta, cos_theta): (f64, f64) = self.rotation.to_radians().sin_cos();
+        let u: f64 = (surface_coords.u - 0.5) * self.scale.u;
+        let v: f64 = (surface_coords.v - 0.5) * self.scale.v;
+
+        let transformed: SurfaceCoordinate = SurfaceCoordinate {
+            u: cos_theta * u - sin_theta * v + 0.5 + self.offset.u,
+            v: sin_theta * u + cos_theta * v + 0.5 + self.offset.v,
+        };
+
+        match self.tiles {
+            Some(tiles) => SurfaceCoordinate {
+                u: (transformed.u * tiles.u).rem_euclid(1.0),
+                v: (transformed.v * tiles.v).rem_euclid(1.0),
+            },
+            None => transformed,
+        }
+    }
+}
+
+impl Texture for TransformedTexture {
+    fn value(&self, surface_coords: SurfaceCoordinate, p: &Point3) -> Color {
+        self.filtered_value(surface_coords, p, SurfaceCoordinate { u: 0.0, v: 0.0 })
+    }
+
+    fn filtered_value(
+        &self,
+        surface_coords: SurfaceCoordinate,
+        p: &Point3,
+        footprint: SurfaceCoordinate,
+    ) -> Color {
+        let point: Point3 = *p * self.point_scale + self.point_offset;
+
+        // The footprint grows with the coordinates, and the rotation mixes its sides
+        let tiles: SurfaceCoordinate = self.tiles.unwrap_or(SurfaceCoordinate { u: 1.0, v: 1.0 });
+        let u: f64 = (footprint.u * self.scale.u * tiles.u).abs();
+        let v: f64 = (footprint.v * self.scale.v * tiles.v).abs();
+        let (sin_theta, cos_theta): (f64, f64) = self.rotation.to_radians().sin_cos();
+        let footprint: SurfaceCoordinate = SurfaceCoordinate {
+            u: cos_theta.abs() * u + sin_theta.abs() * v,
+            v: sin_theta.abs() * u + cos_theta.abs() * v,
+        };
+
+        self.texture
+            .filtered_value(self.transform(surface_coords), &point, footprint)
+    }
+}
+
+/// How an image texture is sampled between its pixels
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextureFilter {
+    /// The closest pixel
+    Nearest,
+    /// Interpolation of the four closest pixels
+    Bilinear,
+    /// Bilinear in the two levels of the mipmap closest to the footprint of the ray, interpolated between them.
+    /// Far away and grazing surfaces get blurred instead of aliased
+    Trilinear,
+}
+
+/// What an image texture does with surface coordinates outside of 0..1
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WrapMode {
+    /// Tile the image
+    Repeat,
+    /// Tile the image, flipping every other copy so there are no seams
+    Mirror,
+    /// Extend the pixels on the edges
+    Clamp,
+}
+
+impl WrapMode {
+    /// Pixel index inside 0..size for index
+    fn wrap(&self, index: i64, size: usize) -> usize {
+        let size: i64 = size as i64;
+        let wrapped: i64 = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Mirror => {
+                let period: i64 = index.rem_euclid(2 * size);
+                if period < size {
+                    period
+                } else {
+                    2 * size - 1 - period
+                }
+            }
+            WrapMode::Clamp => index.clamp(0, size - 1),
+        };
+
+        wrapped as usize
+    }
+}
+
+/// One level of a mipmap: the image at a resolution
+struct MipLevel {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 3]>,
+}
+
+impl MipLevel {
+    /// The next level, half as big (rounding down), where each pixel is the average of the part of the image it
+    /// covers. For odd sizes that part does not line up with the pixels, so the ones on its edges count partly
+    fn downsample(&self) -> MipLevel {
+        let width: usize = (self.width / 2).max(1);
+        let height: usize = (self.height / 2).max(1);
+        let mut pixels: Vec<[f32; 3]> = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            let rows: Vec<(usize, f32)> = box_filter(y, self.height, height);
+            for x in 0..width {
+                let columns: Vec<(usize, f32)> = box_filter(x, self.width, width);
+
+                let mut sum: [f32; 3] = [0.0; 3];
+                for &(row, row_weight) in &rows {
+                    for &(column, column_weight) in &columns {
+                        let pixel: [f32; 3] = self.pixels[row * self.width + column];
+                        for channel in 0..3 {
+                            sum[channel] += row_weight * column_weight * pixel[channel];
+                        }
+                    }
+                }
+                pixels.push(sum);
+            }
+        }
+
+        MipLevel {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+/// The pixels along one axis of an image of size that pixel index of an image of new_size covers, with how much of
+/// it each one is. The weights add up to 1
+fn box_filter(index: usize, size: usize, new_size: usize) -> Vec<(usize, f32)> {
+    let scale: f64 = size as f64 / new_size as f64;
+    let start: f64 = index as f64 * scale;
+    let end: f64 = start + scale;
+
+    (start.floor() as usize..(end.ceil() as usize).min(size))
+        .map(|i| {
+            let covered: f64 = end.min((i + 1) as f64) - start.max(i as f64);
+            (i, (covered / scale) as f32)
+        })
+        .collect()
+}
+
+/// How the values of an image file are encoded
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorSpace {
+    /// Colors encoded with the sRGB curve, like in almost every .png and .jpg. They are decoded to linear on load
+    Srgb,
+    /// Values that are linear already, like normal, roughness and bump maps, which must not be decoded
+    Linear,
+}
+
+/// Load the image in path as linear rgb. Images with 8 or 16 bits per channel are decoded if color_space is Srgb,
+/// and float images (.hdr, .exr) are always linear, with values that can go above 1
+pub fn load_linear_image(path: &str, color_space: ColorSpace) -> ImageResult<Rgb32FImage> {
+    let image: DynamicImage = open_with_pixels(path)?;
+    let is_float: bool = matches!(
+        image,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+    );
+
+    let mut linear: Rgb32FImage = image.into_rgb32f();
+    if color_space == ColorSpace::Srgb && !is_float {
+        decode_srgb(&mut linear);
+    }
+    Ok(linear)
+}
+
+/// Open the image in path, giving an error for images without any pixel, which textures cannot sample
+fn open_with_pixels(path: &str) -> ImageResult<DynamicImage> {
+    let image: DynamicImage = open(path)?;
+    if image.width() == 0 || image.height() == 0 {
+        return Err(ImageError::Limits(LimitError::from_kind(
+            LimitErrorKind::DimensionError,
+        )));
+    }
+    Ok(image)
+}
+
+fn decode_srgb(image: &mut Rgb32FImage) {
+    for pixel in image.pixels_mut() {
+        pixel.0 = pixel.0.map(|channel| srgb_to_linear(channel as f64) as f32);
+    }
+}
+
+/// An image texture, built on the image crate. Create with new_or_fallback(), or data_or_fallback() for maps that
+/// are not colors. If the path gives an error, load an error texture that is easy to see.
+/// The image is kept as a mipmap: a chain of copies, each half as big as the one before, so far away surfaces can
+/// use a smaller one instead of skipping pixels
+pub struct ImageTexture {
+    levels: Vec<MipLevel>,
+    pub filter: TextureFilter,
+    pub wrap_mode: WrapMode,
+}
+
+impl ImageTexture {
+    /// A color texture, with trilinear filtering and repeating, which suits most of them
+    pub fn new_or_fallback(path: &str) -> Arc<dyn Texture> {
+        ImageTexture::with_sampling_or_fallback(
+            path,
+            TextureFilter::Trilinear,
+            WrapMode::Repeat,
+            ColorSpace::Srgb,
+        )
+    }
+
+    /// A texture whose values are data, like a normal or a roughness map, so they are not decoded as sRGB
+    pub fn data_or_fallback(path: &str) -> Arc<dyn Texture> {
+        ImageTexture::with_sampling_or_fallback(
+            path,
+            TextureFilter::Trilinear,
+            WrapMode::Repeat,
+            ColorSpace::Linear,
+        )
+    }
+
+    pub fn with_sampling_or_fallback(
+        path: &str,
+        filter: TextureFilter,
+        wrap_mode: WrapMode,
+        color_space: ColorSpace,
+    ) -> Arc<dyn Texture> {
+        match load_linear_image(path, color_space) {
+            Ok(image) => Arc::new(ImageTexture::from_linear_image(&image, filter, wrap_mode)),
+            Err(image_error) => {
+                eprintln!("Could not load the image texture. Falling back to default. Error:");
+                eprintln!("{}", image_error);
+
+                CheckerTexture::from_colors(
+                    2.0,
+                    Point3 {
+                        x: 1.0,
+                        y: 0.0,
+                        z: 0.862745098039,
+                    },
+                    Point3 {
+                        x: 0.00392156862745,
+                        y: 0.0,
+                        z: 0.00392156862745,
+                    },
+                )
+            }
+        }
+    }
+
+    /// A texture from an 8 bit image already in memory
+    pub fn from_image(
+        image: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+        filter: TextureFilter,
+        wrap_mode: WrapMode,
+        color_space: ColorSpace,
+    ) -> ImageTexture {
+        let mut linear: Rgb32FImage = DynamicImage::ImageRgb8(image.clone()).into_rgb32f();
+        if color_space == ColorSpace::Srgb {
+            decode_srgb(&mut linear);
+        }
+
+        ImageTexture::from_linear_image(&linear, filter, wrap_mode)
+    }
+
+    /// A texture from a linear float image already in memory
+    pub fn from_linear_image(
+        image: &Rgb32FImage,
+        filter: TextureFilter,
+        wrap_mode: WrapMode,
+    ) -> ImageTexture {
+        if image.width() == 0 || image.height() == 0 {
+            panic!(
+                "an image texture needs at least one pixel, got {}x{}",
+                image.width(),
+                image.height()
+            );
+        }
+        let mut levels: Vec<MipLevel> = vec![MipLevel {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            pixels: image.pixels().map(|pixel| pixel.0).collect(),
+        }];
+
+        while let Some(last) = levels.last()
+            && (last.width > 1 || last.height > 1)
+        {
+            let next: MipLevel = last.downsample();
+            levels.push(next);
+        }
+
+        ImageTexture {
+            levels,
+            filter,
+            wrap_mode,
+        }
+    }
+
+    /// Number of levels of the mipmap, from the full image down to a single pixel
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    fn pixel(&self, level: &MipLevel, x: i64, y: i64) -> Color {
+        let x: usize = self.wrap_mode.wrap(x, level.width);
+        let y: usize = self.wrap_mode.wrap(y, level.height);
+        let pixel: [f32; 3] = level.pixels[y * level.width + x];
+
+        Color::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64)
+    }
+
+    /// The closest pixel of level to surface_coords
+    fn nearest(&self, level: usize, surface_coords: SurfaceCoordinate) -> Color {
+        let level: &MipLevel = &self.levels[level];
+        // Flip v to image coordinates
+        let x: f64 = surface_coords.u * level.width as f64;
+        let y: f64 = (1.0 - surface_coords.v) * level.height as f64;
+
+        self.pixel(level, x.floor() as i64, y.floor() as i64)
+    }
+
+    /// Interpolation of the four pixels of level around surface_coords
+    fn bilinear(&self, level: usize, surface_coords: SurfaceCoordinate) -> Color {
+        let level: &MipLevel = &self.levels[level];
+        // Relative to the centers of the pixels
+        let x: f64 = surface_coords.u * level.width as f64 - 0.5;
+        let y: f64 = (1.0 - surface_coords.v) * level.height as f64 - 0.5;
+        let (x0, y0): (f64, f64) = (x.floor(), y.floor());
+        let (tx, ty): (f64, f64) = (x - x0, y - y0);
+        let (x0, y0): (i64, i64) = (x0 as i64, y0 as i64);
+
+        let top: Color =
+            (1.0 - tx) * self.pixel(level, x0, y0) + tx * self.pixel(level, x0 + 1, y0);
+        let bottom: Color =
+            (1.0 - tx) * self.pixel(level, x0, y0 + 1) + tx * self.pixel(level, x0 + 1, y0 + 1);
+
+        (1.0 - ty) * top + ty * bottom
+    }
+}
+
+impl ImageTexture {
+    /// The alpha channel of an image as a grey texture, for example to use it as the opacity of a Cutout.
+    /// Images without alpha are fully opaque. If the path gives an error the texture is white (opaque)
+    pub fn alpha_or_fallback(path: &str) -> Arc<dyn Texture> {
+        match open_with_pixels(path) {
+            Ok(image) => {
+                // Alpha is always linear
+                let rgba: Rgba32FImage = image.into_rgba32f();
+                let alpha: Rgb32FImage =
+                    ImageBuffer::from_fn(rgba.width(), rgba.height(), |x, y| {
+                        let alpha: f32 = rgba.get_pixel(x, y).0[3];
+                        Rgb([alpha, alpha, alpha])
+                    });
+                Arc::new(ImageTexture::from_linear_image(
+                    &alpha,
+                    TextureFilter::Bilinear,
+                    WrapMode::Repeat,
+                ))
+            }
+            Err(image_error) => {
+                eprintln!("Could not load the alpha of the image. Falling back to opaque. Error:");
+                eprintln!("{}", image_error);
+
+                SolidColor::new(Color::white())
+            }
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, surface_coords: SurfaceCoordinate, p: &Point3) -> Point3 {
+        self.filtered_value(surface_coords, p, SurfaceCoordinate { u: 0.0, v: 0.0 })
+    }
+
+    fn filtered_value(
+        &self,
+        surface_coords: SurfaceCoordinate,
+        _p: &Point3,
+        footprint: SurfaceCoordinate,
+    ) -> Color {
+        match self.filter {
+            TextureFilter::Nearest => self.nearest(0, surface_coords),
+            TextureFilter::Bilinear => self.bilinear(0, surface_coords),
+            TextureFilter::Trilinear => {
+                // Width of the footprint in pixels of the full image, which halves with each level. The longest
+                // side, which blurs more but does not alias
+                let pixels: f64 = (footprint.u * self.levels[0].width as f64)
+                    .max(footprint.v * self.levels[0].height as f64);
+                if pixels <= 1.0 {
+                    return self.bilinear(0, surface_coords);
+                }
+
+                let level: f64 = pixels.log2().min((self.levels.len() - 1) as f64);
+                let lower: usize = level.floor() as usize;
+                let upper: usize = (lower + 1).min(self.levels.len() - 1);
+                let t: f64 = level - lower as f64;
+
+                (1.0 - t) * self.bilinear(lower, surface_coords)
+                    + t * self.bilinear(upper, surface_coords)
+            }
+        }
+    }
+}
+
+/// The marble of the books. procedural::MarbleTexture has the same look with its parameters exposed
+pub struct PerlinNoiseTexture {
+    pub perlin_noise: PerlinNoise,
+    pub scale: f64,
+}
+
+impl Texture for PerlinNoiseTexture {
+    fn value(&self, _surface_coords: SurfaceCoordinate, p: &Point3) -> Point3 {
+        // To do: this point is dereferenced so it can be multiplied. Improve?
+        // Basic perlin noise
+        // let p: Point3 = *p*self.scale;
+        // Point3 { x: 1.0, y: 1.0, z: 1.0 } * 0.5 * (1.0 + self.perlin_noise.noise(&p))
+        // Turbulent perlin noise
+        // Point3 { x: 1.0, y: 1.0, z: 1.0 } * self.perlin_noise.turbulence(&p, 7)
+        // Turbulent perlin noise modulated with a sine
+        Point3 {
+            x: 0.5,
+            y: 0.5,
+            z: 0.5,
+        } * (1.0 + (self.scale * p.z + 10.0 * self.perlin_noise.turbulence(p, 7)).sin())
+    }
+}
+
+/// How fractal noise is turned into a value between 0 and 1
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NoiseStyle {
+    /// The noise moved from -1..1 to 0..1, soft like clouds
+    Smooth,
+    /// The absolute value of the noise, with sharp creases like veins or cracks
+    Turbulent,
+}
+
+/// Fractal noise that blends between two colors. Use new() for any parameters or one of the presets
+pub struct FbmTexture {
+    pub perlin_noise: PerlinNoise,
+    pub fbm: Fbm,
+    /// Number of times the noise is repeated per unit of distance
+    pub frequency: f64,
+    pub style: NoiseStyle,
+    /// Colors where the noise is 0 and where it is 1
+    pub low: Color,
+    pub high: Color,
+}
+
+impl FbmTexture {
+    pub fn new(
+        seed: u64,
+        frequency: f64,
+        fbm: Fbm,
+        style: NoiseStyle,
+        low: Color,
+        high: Color,
+    ) -> Arc<FbmTexture> {
+        Arc::new(FbmTexture {
+            perlin_noise: PerlinNoise::new(seed),
+            fbm,
+            frequency,
+            style,
+            low,
+            high,
+        })
+    }
+
+    /// Soft white clouds over a blue sky
+    pub fn clouds(seed: u64, frequency: f64) -> Arc<FbmTexture> {
+        FbmTexture::new(
+            seed,
+            frequency,
+            Fbm::new(6),
+            NoiseStyle::Smooth,
+            Color::new(0.25, 0.45, 0.85),
+            Color::white(),
+        )
+    }
+
+    /// Rough grey stone, with strong small details
+    pub fn rock(seed: u64, frequency: f64) -> Arc<FbmTexture> {
+        FbmTexture::new(
+            seed,
+            frequency,
+            Fbm::new(8).with_gain(0.6).with_lacunarity(2.2),
+            NoiseStyle::Turbulent,
+            Color::new(0.45, 0.42, 0.38),
+            Color::new(0.15, 0.14, 0.13),
+        )
+    }
+
+    /// Dark creases on a lighter background, like the cracks of dry mud
+    pub fn cracks(seed: u64, frequency: f64) -> Arc<FbmTexture> {
+        FbmTexture::new(
+            seed,
+            frequency,
+            Fbm::new(4),
+            NoiseStyle::Turbulent,
+            Color::new(0.1, 0.07, 0.05),
+            Color::new(0.55, 0.42, 0.3),
+        )
+    }
+
+    /// The noise at p, between 0 and 1
+    pub fn amount(&self, p: &Point3) -> f64 {
+        let p: Point3 = self.frequency * *p;
+
+        let value: f64 = match self.style {
+            NoiseStyle::Smooth => 0.5 + 0.5 * self.perlin_noise.fbm(&p, &self.fbm),
+            NoiseStyle::Turbulent => self.perlin_noise.turbulence_with(&p, &self.fbm),
+        };
+
+        value.clamp(0.0, 1.0)
+    }
+}
+
+impl Texture for FbmTexture {
+    fn value(&self, _surface_coords: SurfaceCoordinate, p: &Point3) -> Color {
+        let t: f64 = self.amount(p);
+
+        (1.0 - t) * self.low + t * self.high
+    }
+}
+
+pub mod procedural;
@@ -0,0 +1,313 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::SurfaceCoordinate,
+    noise::{SimplexNoise, WorleyDistance, WorleyNoise},
+    perlin::{Fbm, PerlinNoise},
+    point3::{Point3, color::Color},
+    texture::Texture,
+};
+
+/// Colors at positions from 0 to 1, blended linearly between them. Values before the first stop have its color, and
+/// values after the last one too
+#[derive(Clone, Debug)]
+pub struct ColorRamp {
+    /// Sorted by position
+    stops: Vec<(f64, Color)>,
+}
+
+impl ColorRamp {
+    /// A ramp through the stops, given as (position, color) in any order
+    pub fn new(mut stops: Vec<(f64, Color)>) -> ColorRamp {
+        if stops.is_empty() {
+            panic!("a color ramp needs at least one stop");
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        ColorRamp { stops }
+    }
+
+    /// From black at 0 to white at 1
+    pub fn grey() -> ColorRamp {
+        ColorRamp::two_colors(Color::black(), Color::white())
+    }
+
+    pub fn two_colors(low: Color, high: Color) -> ColorRamp {
+        ColorRamp::new(vec![(0.0, low), (1.0, high)])
+    }
+
+    pub fn stops(&self) -> &[(f64, Color)] {
+        &self.stops
+    }
+
+    pub fn at(&self, t: f64) -> Color {
+        // The first stop after t, which is blended with the one before it
+        let after: usize = self.stops.partition_point(|stop| stop.0 <= t);
+        if after == 0 {
+            return self.stops[0].1;
+        }
+        if after == self.stops.len() {
+            return self.stops[after - 1].1;
+        }
+
+        let (start, low): (f64, Color) = self.stops[after - 1];
+        let (end, high): (f64, Color) = self.stops[after];
+        let s: f64 = (t - start) / (end - start);
+
+        (1.0 - s) * low + s * high
+    }
+}
+
+/// Any texture mapped through a color ramp, using its luminance clamped to 0..1. Turns a greyscale texture, like a
+/// noise or a loaded height map, into colors
+pub struct ColorRampTexture {
+    pub input: Arc<dyn Texture>,
+    pub ramp: ColorRamp,
+}
+
+impl ColorRampTexture {
+    pub fn new(input: Arc<dyn Texture>, ramp: ColorRamp) -> Arc<ColorRampTexture> {
+        Arc::new(ColorRampTexture { input, ramp })
+    }
+}
+
+impl Texture for ColorRampTexture {
+    fn value(&self, surface_coords: SurfaceCoordinate, p: &Point3) -> Color {
+        let t: f64 = self
+            .input
+            .value(surface_coords, p)
+            .luminance()
+            .clamp(0.0, 1.0);
+
+        self.ramp.at(t)
+    }
+
+    fn filtered_value(
+        &self,
+        surface_coords: SurfaceCoordinate,
+        p: &Point3,
+        footprint: SurfaceCoordinate,
+    ) -> Color {
+        let t: f64 = self
+            .input
+            .filtered_value(surface_coords, p, footprint)
+            .luminance()
+            .clamp(0.0, 1.0);
+
+        self.ramp.at(t)
+    }
+}
+
+/// Veins of turbulence along a sine wave over z. The parameterised version of PerlinNoiseTexture
+pub struct MarbleTexture {
+    pub perlin_noise: PerlinNoise,
+    /// Number of stripes per unit of distance, times 2 pi
+    pub frequency: f64,
+    /// How much the turbulence bends the stripes
+    pub distortion: f64,
+    pub fbm: Fbm,
+    pub ramp: ColorRamp,
+}
+
+impl MarbleTexture {
+    pub fn new(
+        seed: u64,
+        frequency: f64,
+        distortion: f64,
+        fbm: Fbm,
+        ramp: ColorRamp,
+    ) -> Arc<MarbleTexture> {
+        Arc::new(MarbleTexture {
+            perlin_noise: PerlinNoise::new(seed),
+            frequency,
+            distortion,
+            fbm,
+            ramp,
+        })
+    }
+
+    /// White marble with grey veins, like the one of the books
+    pub fn white(seed: u64, frequency: f64) -> Arc<MarbleTexture> {
+        MarbleTexture::new(
+            seed,
+            frequency,
+            10.0,
+            Fbm::new(7),
+            ColorRamp::new(vec![
+                (0.0, Color::new(0.3, 0.3, 0.32)),
+                (0.4, Color::new(0.75, 0.75, 0.75)),
+                (1.0, Color::new(0.95, 0.94, 0.92)),
+            ]),
+        )
+    }
+
+    /// Dark green stone with pale veins
+    pub fn green(seed: u64, frequency: f64) -> Arc<MarbleTexture> {
+        MarbleTexture::new(
+            seed,
+            frequency,
+            6.0,
+            Fbm::new(6),
+            ColorRamp::new(vec![
+                (0.0, Color::new(0.75, 0.85, 0.78)),
+                (0.15, Color::new(0.1, 0.3, 0.2)),
+                (1.0, Color::new(0.02, 0.1, 0.06)),
+            ]),
+        )
+    }
+
+    /// The value at p, between 0 and 1
+    pub fn amount(&self, p: &Point3) -> f64 {
+        let turbulence: f64 = self.perlin_noise.turbulence_with(p, &self.fbm);
+
+        0.5 * (1.0 + (self.frequency * p.z + self.distortion * turbulence).sin())
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _surface_coords: SurfaceCoordinate, p: &Point3) -> Color {
+        self.ramp.at(self.amount(p))
+    }
+}
+
+/// Growth rings around the vertical axis through the origin, wobbled by noise. Move or rotate the object, or use
+/// TransformedTexture::with_point_transform, to place the trunk
+pub struct WoodTexture {
+    pub perlin_noise: PerlinNoise,
+    /// Number of rings per unit of distance from the axis
+    pub rings: f64,
+    /// How much the noise moves the rings, in rings
+    pub distortion: f64,
+    /// Number of times the noise is repeated per unit of distance
+    pub noise_frequency: f64,
+    /// Colors across a ring, from its inner edge at 0 to its outer edge at 1
+    pub ramp: ColorRamp,
+}
+
+impl WoodTexture {
+    pub fn new(
+        seed: u64,
+        rings: f64,
+        distortion: f64,
+        noise_frequency: f64,
+        ramp: ColorRamp,
+    ) -> Arc<WoodTexture> {
+        Arc::new(WoodTexture {
+            perlin_noise: PerlinNoise::new(seed),
+            rings,
+            distortion,
+            noise_frequency,
+            ramp,
+        })
+    }
+
+    /// Light oak, with thin dark late wood at the end of each ring
+    pub fn oak(seed: u64, rings: f64) -> Arc<WoodTexture> {
+        WoodTexture::new(
+            seed,
+            rings,
+            0.6,
+            2.0,
+            ColorRamp::new(vec![
+                (0.0, Color::new(0.72, 0.52, 0.3)),
+                (0.7, Color::new(0.62, 0.42, 0.22)),
+                (0.9, Color::new(0.35, 0.2, 0.09)),
+                (1.0, Color::new(0.72, 0.52, 0.3)),
+            ]),
+        )
+    }
+
+    /// The position of p across its ring, between 0 and 1
+    pub fn amount(&self, p: &Point3) -> f64 {
+        let radius: f64 = (p.x * p.x + p.z * p.z).sqrt();
+        let noise: f64 = self.perlin_noise.noise(&(self.noise_frequency * *p));
+
+        (self.rings * radius + self.distortion * noise).rem_euclid(1.0)
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, _surface_coords: SurfaceCoordinate, p: &Point3) -> Color {
+        self.ramp.at(self.amount(p))
+    }
+}
+
+/// Cells of Worley noise, mapped through a ramp. The distance is 0 at the feature points for Closest, and 0 at the
+/// borders between cells for Border. Distances above 1 are clamped
+pub struct WorleyTexture {
+    pub worley_noise: WorleyNoise,
+    /// Number of cells per unit of distance
+    pub frequency: f64,
+    pub distance: WorleyDistance,
+    pub ramp: ColorRamp,
+}
+
+impl WorleyTexture {
+    pub fn new(
+        seed: u64,
+        frequency: f64,
+        distance: WorleyDistance,
+        ramp: ColorRamp,
+    ) -> Arc<WorleyTexture> {
+        Arc::new(WorleyTexture {
+            worley_noise: WorleyNoise::new(seed),
+            frequency,
+            distance,
+            ramp,
+        })
+    }
+
+    /// Light cells separated by thin dark lines, like scales or cobblestones
+    pub fn cells(seed: u64, frequency: f64, low: Color, high: Color) -> Arc<WorleyTexture> {
+        WorleyTexture::new(
+            seed,
+            frequency,
+            WorleyDistance::Border,
+            ColorRamp::new(vec![(0.0, low), (0.12, high)]),
+        )
+    }
+
+    pub fn amount(&self, p: &Point3) -> f64 {
+        self.worley_noise
+            .noise(&(self.frequency * *p), self.distance)
+            .clamp(0.0, 1.0)
+    }
+}
+
+impl Texture for WorleyTexture {
+    fn value(&self, _surface_coords: SurfaceCoordinate, p: &Point3) -> Color {
+        self.ramp.at(self.amount(p))
+    }
+}
+
+/// Fractal simplex noise, moved to 0..1 and mapped through a ramp. Like FbmTexture, without the grid of Perlin noise
+pub struct SimplexTexture {
+    pub simplex_noise: SimplexNoise,
+    /// Number of times the noise is repeated per unit of distance
+    pub frequency: f64,
+    pub fbm: Fbm,
+    pub ramp: ColorRamp,
+}
+
+impl SimplexTexture {
+    pub fn new(seed: u64, frequency: f64, fbm: Fbm, ramp: ColorRamp) -> Arc<SimplexTexture> {
+        Arc::new(SimplexTexture {
+            simplex_noise: SimplexNoise::new(seed),
+            frequency,
+            fbm,
+            ramp,
+        })
+    }
+
+    pub fn amount(&self, p: &Point3) -> f64 {
+        let noise: f64 = self.simplex_noise.fbm(&(self.frequency * *p), &self.fbm);
+
+        (0.5 + 0.5 * noise).clamp(0.0, 1.0)
+    }
+}
+
+impl Texture for SimplexTexture {
+    fn value(&self, _surface_coords: SurfaceCoordinate, p: &Point3) -> Color {
+        self.ramp.at(self.amount(p))
+    }
+}